@@ -0,0 +1,290 @@
+use ratatui::style::Color;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::{desktop_entries, logging};
+
+/// Bundled rules, used after any user overrides
+const DEFAULT_APPS: &str = include_str!("apps.toml");
+
+static REGISTRY: LazyLock<RwLock<Arc<AppRegistry>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(AppRegistry::load())));
+
+#[derive(Deserialize, Debug, Default)]
+struct AppsFile {
+    #[serde(default)]
+    apps: Vec<AppRuleConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct AppRuleConfig {
+    #[serde(default)]
+    class: Vec<String>,
+    #[serde(default)]
+    title_prefix: Vec<String>,
+    #[serde(default)]
+    title_regex: Option<String>,
+    #[serde(default)]
+    terminal: bool,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    bg: Option<String>,
+    #[serde(default)]
+    fg: Option<String>,
+}
+
+#[derive(Debug)]
+struct AppRule {
+    /// Lowercased window classes
+    class: Vec<String>,
+    /// Lowercased title prefixes
+    title_prefix: Vec<String>,
+    title_regex: Option<Regex>,
+    /// Windows of this class show the program running inside, so titles are checked first
+    terminal: bool,
+    icon: Option<String>,
+    colors: Option<(Color, Color)>,
+}
+
+impl AppRule {
+    fn from_config(config: AppRuleConfig) -> color_eyre::Result<Self> {
+        let title_regex = config
+            .title_regex
+            .map(|r| RegexBuilder::new(&r).case_insensitive(true).build())
+            .transpose()?;
+        let colors = match config.bg {
+            Some(bg) => {
+                let fg = config.fg.as_deref().unwrap_or("white");
+                Some((parse_color(&bg)?, parse_color(fg)?))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            class: config.class.iter().map(|c| c.to_lowercase()).collect(),
+            title_prefix: config
+                .title_prefix
+                .iter()
+                .map(|p| p.to_lowercase())
+                .collect(),
+            title_regex,
+            terminal: config.terminal,
+            icon: config.icon,
+            colors,
+        })
+    }
+
+    fn matches_class(&self, class: &str) -> bool {
+        self.class.iter().any(|c| c == class)
+    }
+
+    fn matches_title(&self, title: &str) -> bool {
+        self.title_prefix
+            .iter()
+            .any(|p| title.starts_with(p.as_str()))
+            || self.title_regex.as_ref().is_some_and(|r| r.is_match(title))
+    }
+}
+
+fn parse_color(value: &str) -> color_eyre::Result<Color> {
+    Color::from_str(value).map_err(|_| color_eyre::eyre::eyre!("Invalid color '{}'", value))
+}
+
+/// Icon and brand colors for an application; `None` where no rule provided one
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AppStyle {
+    pub icon: Option<String>,
+    /// Background and foreground
+    pub colors: Option<(Color, Color)>,
+}
+
+impl AppStyle {
+    fn fill_from(&mut self, rule: &AppRule) {
+        if self.icon.is_none() {
+            self.icon = rule.icon.clone();
+        }
+        if self.colors.is_none() {
+            self.colors = rule.colors;
+        }
+    }
+}
+
+/// Ordered icon/color rules from `~/.config/catfood/apps.toml` followed by the bundled table
+#[derive(Debug, Default)]
+pub struct AppRegistry {
+    rules: Vec<AppRule>,
+}
+
+impl AppRegistry {
+    /// Load the user overrides (if any) on top of the bundled rules
+    pub fn load() -> Self {
+        let user = match std::fs::read_to_string(user_apps_path()) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                logging::log_config_error(&format!("Failed to read apps.toml: {}", e));
+                None
+            }
+        };
+        Self::from_sources(user.as_deref())
+    }
+
+    fn from_sources(user: Option<&str>) -> Self {
+        let mut rules = Vec::new();
+        if let Some(user) = user {
+            match parse_rules(user) {
+                Ok(user_rules) => rules.extend(user_rules),
+                Err(e) => logging::log_config_error(&format!("Invalid apps.toml: {}", e)),
+            }
+        }
+        match parse_rules(DEFAULT_APPS) {
+            Ok(default_rules) => rules.extend(default_rules),
+            Err(e) => logging::log_config_error(&format!("Invalid bundled apps table: {}", e)),
+        }
+        Self { rules }
+    }
+
+    /// Style for a window of the given class, or for a terminal tab when `class` is `None`.
+    ///
+    /// Titles are only matched for terminals, where they name the program running inside.
+    pub fn lookup(&self, class: Option<&str>, title: &str) -> AppStyle {
+        let class = class.map(str::to_lowercase);
+        let class_rules: Vec<&AppRule> = match &class {
+            Some(class) => self
+                .rules
+                .iter()
+                .filter(|r| r.matches_class(class))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut style = AppStyle::default();
+        if class.is_none() || class_rules.iter().any(|r| r.terminal) {
+            let title = title.to_lowercase();
+            for rule in self.rules.iter().filter(|r| r.matches_title(&title)) {
+                style.fill_from(rule);
+            }
+        }
+        for rule in class_rules {
+            style.fill_from(rule);
+        }
+        style
+    }
+}
+
+fn parse_rules(content: &str) -> color_eyre::Result<Vec<AppRule>> {
+    let file: AppsFile = toml::from_str(content)?;
+    file.apps.into_iter().map(AppRule::from_config).collect()
+}
+
+pub fn user_apps_path() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+        .join(".config")
+        .join("catfood")
+        .join("apps.toml")
+}
+
+/// The current registry, loaded on first use
+pub fn registry() -> Arc<AppRegistry> {
+    REGISTRY
+        .read()
+        .map(|r| r.clone())
+        .unwrap_or_else(|_| Arc::new(AppRegistry::default()))
+}
+
+/// Re-read the user overrides, e.g. after `apps.toml` changed
+pub fn reload() {
+    if let Ok(mut registry) = REGISTRY.write() {
+        *registry = Arc::new(AppRegistry::load());
+    }
+    // Also picks up apps installed since the last scan
+    desktop_entries::invalidate();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_rules_parse() {
+        let registry = AppRegistry::from_sources(None);
+        assert_eq!(
+            registry.rules.len(),
+            parse_rules(DEFAULT_APPS).unwrap().len()
+        );
+        assert!(!registry.rules.is_empty());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let registry = AppRegistry::from_sources(None);
+
+        let firefox = registry.lookup(Some("Firefox"), "nvim - Mozilla Firefox");
+        assert_eq!(firefox.icon.as_deref(), Some("󰈹"));
+        assert_eq!(
+            firefox.colors,
+            Some((Color::Rgb(255, 119, 0), Color::Black))
+        );
+
+        // Terminal windows are styled after the program in the title
+        let nvim = registry.lookup(Some("kitty"), "nvim src/main.rs");
+        assert_eq!(nvim.icon.as_deref(), Some("\u{e62b}"));
+        assert_eq!(nvim.colors, Some((Color::Rgb(0, 107, 84), Color::White)));
+
+        // Missing fields fall through to later rules
+        let nano = registry.lookup(Some("kitty"), "nano notes.txt");
+        assert_eq!(nano.icon.as_deref(), Some("\u{e7c5}"));
+        assert_eq!(nano.colors, Some((Color::Rgb(103, 117, 140), Color::White)));
+
+        let cargo = registry.lookup(None, "Running Cargo build");
+        assert_eq!(cargo.icon.as_deref(), Some("󱘗"));
+
+        assert_eq!(
+            registry.lookup(Some("unknown"), "nvim"),
+            AppStyle::default()
+        );
+    }
+
+    #[test]
+    fn test_user_overrides() {
+        let registry = AppRegistry::from_sources(Some(
+            r##"
+            [[apps]]
+            class = ["firefox"]
+            icon = "F"
+
+            [[apps]]
+            class = ["wezterm"]
+            terminal = true
+            bg = "#000000"
+
+            [[apps]]
+            title_regex = "^just "
+            icon = "J"
+            "##,
+        ));
+
+        let firefox = registry.lookup(Some("firefox"), "");
+        assert_eq!(firefox.icon.as_deref(), Some("F"));
+        // Colors still come from the bundled rule
+        assert_eq!(
+            firefox.colors,
+            Some((Color::Rgb(255, 119, 0), Color::Black))
+        );
+
+        let wezterm = registry.lookup(Some("org.wezfurlong.WezTerm"), "just test");
+        assert_eq!(wezterm, AppStyle::default());
+        let wezterm = registry.lookup(Some("wezterm"), "just test");
+        assert_eq!(wezterm.icon.as_deref(), Some("J"));
+        assert_eq!(wezterm.colors, Some((Color::Rgb(0, 0, 0), Color::White)));
+
+        // An invalid override file is ignored rather than dropping the bundled rules
+        let registry = AppRegistry::from_sources(Some("[[apps]]\nbg = \"not a color\""));
+        assert!(registry.lookup(Some("firefox"), "").icon.is_some());
+    }
+}
@@ -0,0 +1,161 @@
+//! Running external tools without blocking the render loop
+//!
+//! Commands run on the tokio runtime, are killed when they exceed their timeout, and share a
+//! small pool of slots so a burst of slow tools can't pile up processes. Long-running helpers
+//! such as the tmux control client or the kitten panel are started here too, but neither take
+//! a slot nor time out.
+
+use crate::wakeup;
+use std::ffi::OsStr;
+use std::process::{Child, Output, Stdio};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::sync::{Semaphore, oneshot};
+
+/// Long enough for a busy tool, short enough that a hung one is noticed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// External commands allowed to run at once; the rest wait for a slot
+const MAX_CONCURRENT: usize = 4;
+
+static SLOTS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(MAX_CONCURRENT));
+
+/// Run `program` once a slot is free, killing it if it runs longer than `timeout`
+pub async fn run(program: &str, args: &[&str], timeout: Duration) -> color_eyre::Result<Output> {
+    let _slot = SLOTS.acquire().await?;
+
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Dropping the child on timeout kills it; tokio reaps it in the background
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => Ok(output?),
+        Err(_) => Err(color_eyre::eyre::eyre!(
+            "`{}` timed out after {:?} and was killed",
+            program,
+            timeout
+        )),
+    }
+}
+
+/// Start [`run`] in the background, for components whose `update` can't wait on it
+pub fn spawn(program: &str, args: &[&str], timeout: Duration) -> PendingCommand {
+    let (tx, rx) = oneshot::channel();
+    let program = program.to_string();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+    tokio::spawn(async move {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let _ = tx.send(run(&program, &args, timeout).await);
+        wakeup::notify();
+    });
+
+    PendingCommand { rx }
+}
+
+/// Start a long-running helper with piped stdin and stdout, e.g. a control mode client.
+///
+/// The caller owns the child and must kill and wait on it once done.
+pub fn attach<S: AsRef<OsStr>>(program: &str, args: &[S]) -> std::io::Result<Child> {
+    std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+/// Launch `program` to outlive the bar, e.g. the kitten panel, without waiting on it
+pub fn detach<S: AsRef<OsStr>>(program: &str, args: &[S]) -> std::io::Result<()> {
+    std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+/// A command started with [`spawn`]
+#[derive(Debug)]
+pub struct PendingCommand {
+    rx: oneshot::Receiver<color_eyre::Result<Output>>,
+}
+
+impl PendingCommand {
+    /// The result once the command has finished, or `None` while it's still running
+    pub fn try_take(&mut self) -> Option<color_eyre::Result<Output>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                Some(Err(color_eyre::eyre::eyre!("Command task was cancelled")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_run() {
+        let output = run("sh", &["-c", "echo hello"], DEFAULT_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello\n");
+
+        assert!(
+            run("catfood-missing-tool", &[], DEFAULT_TIMEOUT)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("finished");
+        let script = format!("sleep 1; touch {}", marker.display());
+
+        let start = Instant::now();
+        let result = run("sh", &["-c", &script], Duration::from_millis(100)).await;
+        assert!(result.unwrap_err().to_string().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Killed, so it never gets to finish
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_spawn() {
+        let mut pending = spawn("sh", &["-c", "echo hello"], DEFAULT_TIMEOUT);
+        let output = loop {
+            if let Some(result) = pending.try_take() {
+                break result.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(output.stdout, b"hello\n");
+    }
+
+    #[test]
+    fn test_attach() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let mut child = attach("sh", &["-c", "read line; echo \"got $line\""]).unwrap();
+        writeln!(child.stdin.take().unwrap(), "hello").unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        assert_eq!(line, "got hello\n");
+        assert!(child.wait().unwrap().success());
+    }
+}
@@ -1,29 +1,42 @@
+use crate::components::sparkline::SparklineLayout;
+use crate::components::tmux::SessionFilter;
+use crate::components::windows::WindowMode;
+use crate::components::workspaces::WindowIndicator;
 use crate::components::{
-    Battery, Brightness, Cpu, ErrorIcon, KittyTabs, Ram, Separator, Space, Temperature, Time,
-    Volume, Weather, Wifi, Windows, Workspaces,
+    Battery, Brightness, Cpu, ErrorIcon, Failed, KittyTabs, Microphone, Network, Ram, Separator,
+    Space, Temperature, Time, Tmux, Volume, Weather, Wifi, Windows, Workspaces,
 };
 use crate::config::{ComponentConfig, Config};
+use crate::logging;
 use crate::lua_component::{LuaComponent, LuaComponentRegistry};
-use ratatui::{prelude::Stylize, text::Span};
+use ratatui::text::Span;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
 
 #[derive(Debug)]
 pub enum Component {
     Workspaces(Workspaces),
     Windows(Windows),
     KittyTabs(KittyTabs),
+    Tmux(Tmux),
     Time(Time),
     Weather(Weather),
     Temperature(Temperature),
     Cpu(Cpu),
     Ram(Ram),
     Wifi(Wifi),
+    Network(Network),
     Brightness(Brightness),
     Volume(Volume),
+    Microphone(Microphone),
     Battery(Battery),
     Separator(Separator),
     Space(Space),
     ErrorIcon(ErrorIcon),
+    Failed(Failed),
     Lua(LuaComponent),
 }
 
@@ -35,15 +48,29 @@ impl Component {
     ) -> color_eyre::Result<Self> {
         let component_type = component_config.name();
         match component_type {
-            "workspaces" => Ok(Component::Workspaces(Workspaces::new())),
-            "windows" => Ok(Component::Windows(Windows::new())),
+            "workspaces" => Ok(Component::Workspaces(Workspaces::with_config(
+                component_config.monitor(),
+                component_config.workspace_labels().unwrap_or_default(),
+                component_config.persistent_workspaces().unwrap_or_default(),
+                WindowIndicator::from_config(component_config.window_indicator().as_deref()),
+            ))),
+            "windows" => Ok(Component::Windows(Windows::with_config(
+                component_config.monitor(),
+                WindowMode::from_config(component_config.window_mode().as_deref()),
+                component_config.max_windows(),
+                component_config.max_title_length(),
+            ))),
             "kitty_tabs" => {
                 let socket_path = component_config.socket_path();
                 Ok(Component::KittyTabs(KittyTabs::with_config(socket_path)))
             }
+            "tmux" => Ok(Component::Tmux(Tmux::with_config(
+                SessionFilter::from_config(component_config.session().as_deref()),
+            ))),
             "time" => Ok(Component::Time(Time::with_config(
                 config.day_start,
                 config.night_start,
+                component_config.format(),
             ))),
             "weather" => Ok(Component::Weather(Weather::with_config(
                 config.day_start,
@@ -82,16 +109,34 @@ impl Component {
                 let sparkline_update_freq = component_config.sparkline_update_freq().unwrap_or(2);
                 let sparkline_logarithmic =
                     component_config.sparkline_logarithmic().unwrap_or(true); // Logarithmic by default for WiFi
+                let sparkline_layout =
+                    SparklineLayout::from_config(component_config.sparkline_layout().as_deref());
                 Ok(Component::Wifi(Wifi::with_config(
                     sparkline,
                     sparkline_length,
                     sparkline_update_freq,
                     sparkline_logarithmic,
+                    sparkline_layout,
+                    component_config.sparkline_peaks().unwrap_or(false),
+                    component_config.format(),
                 )))
             }
-            "brightness" => Ok(Component::Brightness(Brightness::new())),
-            "volume" => Ok(Component::Volume(Volume::new())),
-            "battery" => Ok(Component::Battery(Battery::new()?)),
+            "network" => Ok(Component::Network(Network::with_config(
+                component_config.interface(),
+                component_config.format(),
+            ))),
+            "brightness" => Ok(Component::Brightness(Brightness::with_config(
+                component_config.device(),
+            ))),
+            "volume" => Ok(Component::Volume(Volume::with_config(
+                component_config.format(),
+            ))),
+            "microphone" => Ok(Component::Microphone(Microphone::with_config(
+                component_config.format(),
+            ))),
+            "battery" => Ok(Component::Battery(Battery::with_config(
+                component_config.format(),
+            )?)),
             "separator" => Ok(Component::Separator(Separator::new())),
             "space" => Ok(Component::Space(Space::new())),
             _ => {
@@ -101,6 +146,9 @@ impl Component {
                 {
                     return Ok(Component::Lua(lua_component.clone()));
                 }
+                if let Some(reason) = lua_registry.and_then(|r| r.failure(component_type)) {
+                    return Err(color_eyre::eyre::eyre!("{}", reason));
+                }
                 Ok(Component::ErrorIcon(ErrorIcon::new()))
             }
         }
@@ -120,6 +168,10 @@ impl Component {
                 component.update();
                 Ok(())
             }
+            Component::Tmux(component) => {
+                component.update();
+                Ok(())
+            }
             Component::Time(component) => {
                 component.update();
                 Ok(())
@@ -144,6 +196,10 @@ impl Component {
                 component.update();
                 Ok(())
             }
+            Component::Network(component) => {
+                component.update();
+                Ok(())
+            }
             Component::Brightness(component) => {
                 component.update();
                 Ok(())
@@ -152,6 +208,10 @@ impl Component {
                 component.update();
                 Ok(())
             }
+            Component::Microphone(component) => {
+                component.update();
+                Ok(())
+            }
             Component::Battery(component) => {
                 component.update()?;
                 Ok(())
@@ -168,6 +228,10 @@ impl Component {
                 // ErrorIcon doesn't need updates
                 Ok(())
             }
+            Component::Failed(_component) => {
+                // Rebuilt by the ComponentManager once its retry is due
+                Ok(())
+            }
             Component::Lua(component) => {
                 component.update()?;
                 Ok(())
@@ -175,44 +239,85 @@ impl Component {
         }
     }
 
+    /// When the component next needs `update` to be called, or `None` if it only changes
+    /// when a background source wakes the main loop
+    pub fn next_update(&self) -> Option<Instant> {
+        match self {
+            Component::Time(component) => Some(component.next_update()),
+            Component::Temperature(component) => Some(component.next_update()),
+            Component::Cpu(component) => Some(component.next_update()),
+            Component::Ram(component) => Some(component.next_update()),
+            Component::Wifi(component) => Some(component.next_update()),
+            Component::Brightness(component) => Some(component.next_update()),
+            Component::Battery(component) => Some(component.next_update()),
+            Component::Failed(component) => Some(component.next_update()),
+            Component::Lua(component) => Some(component.next_update()),
+            _ => None,
+        }
+    }
+
     pub fn render_as_spans_with_colorize(&self, colorize: bool) -> Vec<Span<'_>> {
         match self {
             Component::Workspaces(component) => component.render_as_spans(colorize),
             Component::Windows(component) => component.render_as_spans(colorize),
             Component::KittyTabs(component) => component.render_as_spans(colorize),
+            Component::Tmux(component) => component.render_as_spans(colorize),
             Component::Time(component) => component.render_as_spans(colorize),
             Component::Weather(component) => component.render_as_spans(colorize),
             Component::Temperature(component) => component.render_as_spans(colorize),
             Component::Cpu(component) => component.render_as_spans(colorize),
             Component::Ram(component) => component.render_as_spans(colorize),
             Component::Wifi(component) => component.render_as_spans(colorize),
+            Component::Network(component) => component.render_as_spans(colorize),
             Component::Brightness(component) => component.render_as_spans(colorize),
             Component::Volume(component) => component.render_as_spans(colorize),
+            Component::Microphone(component) => component.render_as_spans(colorize),
             Component::Battery(component) => component.render_as_spans(colorize),
             Component::Separator(component) => vec![Span::raw(component.render())],
             Component::Space(component) => vec![Span::raw(component.render())],
             Component::ErrorIcon(component) => component.render_as_spans(),
+            Component::Failed(component) => component.render_as_spans(colorize),
             Component::Lua(component) => component.render_as_spans_with_colorize(colorize),
         }
     }
 
+    /// Handle a left click on the span at `span` in this component's rendered output
+    pub fn on_click(&mut self, span: usize) {
+        match self {
+            Component::KittyTabs(component) => component.on_click(span),
+            Component::Tmux(component) => component.on_click(span),
+            Component::Microphone(component) => component.on_click(span),
+            Component::Failed(component) => component.retry_now(),
+            _ => {}
+        }
+    }
+
+    /// Why the component isn't running, if it failed
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Component::Failed(component) => Some(&component.reason),
+            _ => None,
+        }
+    }
+
     pub fn is_muted(&self) -> bool {
         match self {
             Component::Volume(component) => component.is_muted,
+            Component::Microphone(component) => component.is_muted,
             _ => false,
         }
     }
 
+    /// Muted components show their level dimmed; later spans such as the microphone's
+    /// recording indicator keep their own style
     pub fn render_as_spans_with_muting_and_colorize(&self, colorize: bool) -> Vec<Span<'_>> {
-        let spans = self.render_as_spans_with_colorize(colorize);
-        if self.is_muted() {
-            spans
-                .into_iter()
-                .map(|span| span.fg(ratatui::style::Color::DarkGray))
-                .collect()
-        } else {
-            spans
+        let mut spans = self.render_as_spans_with_colorize(colorize);
+        if self.is_muted()
+            && let Some(first) = spans.first_mut()
+        {
+            first.style = first.style.fg(ratatui::style::Color::DarkGray);
         }
+        spans
     }
 }
 
@@ -221,6 +326,10 @@ pub struct ComponentManager {
     components: HashMap<String, Component>,
     config: Config,
     lua_registry: LuaComponentRegistry,
+    /// Hash of each component's spans when last updated, to tell whether a redraw is needed
+    fingerprints: HashMap<String, u64>,
+    /// Consecutive failures of rebuilt components, kept until they update successfully
+    failures: HashMap<String, u32>,
 }
 
 impl ComponentManager {
@@ -236,55 +345,103 @@ impl ComponentManager {
                 .join("components");
         lua_registry.load_from_directory(config_dir.to_str().unwrap())?;
 
-        let mut components = HashMap::new();
-
-        // Create all components (unknown ones become error icons)
-        for component_config in &config.bars.left {
-            let component = Component::new(component_config, Some(&lua_registry), &config)?;
-            components.insert(component_config.name().to_string(), component);
-        }
-
-        for component_config in &config.bars.middle {
-            if !components.contains_key(component_config.name()) {
-                let component = Component::new(component_config, Some(&lua_registry), &config)?;
-                components.insert(component_config.name().to_string(), component);
-            }
-        }
-
-        for component_config in &config.bars.right {
-            if !components.contains_key(component_config.name()) {
-                let component = Component::new(component_config, Some(&lua_registry), &config)?;
-                components.insert(component_config.name().to_string(), component);
-            }
-        }
+        let components = build_components(&config, &lua_registry);
 
         Ok(Self {
             components,
             config,
             lua_registry,
+            fingerprints: HashMap::new(),
+            failures: HashMap::new(),
         })
     }
 
-    pub fn update(&mut self) -> color_eyre::Result<()> {
-        // Update built-in components
-        for component in self.components.values_mut() {
-            component.update()?;
+    /// Update every component, turning errors and panics into a [`Failed`] component, and
+    /// rebuilding failed components once their backoff has passed.
+    ///
+    /// Returns whether any component now renders differently.
+    pub fn update(&mut self) -> color_eyre::Result<bool> {
+        let colorize = self.config.colorize;
+        let mut changed = false;
+        for (name, component) in self.components.iter_mut() {
+            Self::update_component(
+                name,
+                component,
+                &mut self.failures,
+                &self.config,
+                &self.lua_registry,
+            );
+
+            let fingerprint = fingerprint(component, colorize);
+            if self.fingerprints.insert(name.clone(), fingerprint) != Some(fingerprint) {
+                changed = true;
+            }
         }
 
-        Ok(())
+        Ok(changed)
     }
 
-    pub fn get_bar_components(&self, bar: &str) -> Vec<&Component> {
+    fn update_component(
+        name: &str,
+        component: &mut Component,
+        failures: &mut HashMap<String, u32>,
+        config: &Config,
+        lua_registry: &LuaComponentRegistry,
+    ) {
+        if let Component::Failed(failed) = component {
+            if failed.is_due() {
+                // A component may start fine yet fail every update, so keep counting
+                failures.insert(name.to_string(), failed.failures);
+                *component = build_component(&failed.config, lua_registry, config, failed.failures);
+            }
+            return;
+        }
+
+        match isolate(|| component.update()) {
+            Ok(()) => {
+                failures.remove(name);
+            }
+            Err(reason) => {
+                logging::log_component_error(name, &format!("Update failed: {}", reason));
+                if let Some(component_config) = find_config(config, name) {
+                    let failures = failures.get(name).copied().unwrap_or_default() + 1;
+                    *component =
+                        Component::Failed(Failed::new(component_config.clone(), reason, failures));
+                }
+            }
+        }
+    }
+
+    /// The earliest moment any component needs another update
+    pub fn next_update(&self) -> Option<Instant> {
+        self.components
+            .values()
+            .filter_map(Component::next_update)
+            .min()
+    }
+
+    /// Components of a bar in order, with the names they're configured under
+    pub fn get_bar_components(&self, bar: &str) -> Vec<(&str, &Component)> {
         if let Some(component_configs) = self.config.get_components_for_bar(bar) {
             component_configs
                 .iter()
-                .filter_map(|config| self.components.get(config.name()))
+                .filter_map(|config| {
+                    let (name, component) = self.components.get_key_value(config.name())?;
+                    Some((name.as_str(), component))
+                })
                 .collect()
         } else {
             Vec::new()
         }
     }
 
+    /// Forward a click on one of a component's rendered spans
+    pub fn handle_click(&mut self, name: &str, span: usize) {
+        if let Some(component) = self.components.get_mut(name) {
+            component.on_click(span);
+        }
+    }
+
     pub fn get_colorize(&self) -> bool {
         self.config.colorize
     }
@@ -302,33 +459,202 @@ impl ComponentManager {
         self.lua_registry
             .load_from_directory(config_dir.to_str().unwrap())?;
 
-        let mut components = HashMap::new();
+        let components = build_components(&new_config, &self.lua_registry);
+
+        self.config = new_config;
+        self.components = components;
+        self.fingerprints.clear();
+        self.failures.clear();
+        Ok(())
+    }
+}
 
-        // Create all components (unknown ones become error icons)
-        for component_config in &new_config.bars.left {
-            let component =
-                Component::new(component_config, Some(&self.lua_registry), &new_config)?;
-            components.insert(component_config.name().to_string(), component);
+/// Create every configured component; unknown names become error icons and failures
+/// become [`Failed`] components
+fn build_components(
+    config: &Config,
+    lua_registry: &LuaComponentRegistry,
+) -> HashMap<String, Component> {
+    let mut components = HashMap::new();
+    for component_config in config
+        .bars
+        .left
+        .iter()
+        .chain(&config.bars.middle)
+        .chain(&config.bars.right)
+    {
+        let name = component_config.name();
+        if !components.contains_key(name) {
+            let component = build_component(component_config, lua_registry, config, 0);
+            components.insert(name.to_string(), component);
         }
+    }
+    components
+}
 
-        for component_config in &new_config.bars.middle {
-            if !components.contains_key(component_config.name()) {
-                let component =
-                    Component::new(component_config, Some(&self.lua_registry), &new_config)?;
-                components.insert(component_config.name().to_string(), component);
-            }
+/// Create one component; `failures` counts earlier attempts, for backoff
+fn build_component(
+    component_config: &ComponentConfig,
+    lua_registry: &LuaComponentRegistry,
+    config: &Config,
+    failures: u32,
+) -> Component {
+    match isolate(|| Component::new(component_config, Some(lua_registry), config)) {
+        Ok(component) => component,
+        Err(reason) => {
+            logging::log_component_error(
+                component_config.name(),
+                &format!("Failed to start: {}", reason),
+            );
+            Component::Failed(Failed::new(component_config.clone(), reason, failures + 1))
         }
+    }
+}
 
-        for component_config in &new_config.bars.right {
-            if !components.contains_key(component_config.name()) {
-                let component =
-                    Component::new(component_config, Some(&self.lua_registry), &new_config)?;
-                components.insert(component_config.name().to_string(), component);
-            }
+fn fingerprint(component: &Component, colorize: bool) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    component
+        .render_as_spans_with_colorize(colorize)
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+fn find_config<'a>(config: &'a Config, name: &str) -> Option<&'a ComponentConfig> {
+    config
+        .bars
+        .left
+        .iter()
+        .chain(&config.bars.middle)
+        .chain(&config.bars.right)
+        .find(|c| c.name() == name)
+}
+
+thread_local! {
+    /// Set while component code runs under [`isolate`]
+    static ISOLATING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run component code, turning both errors and panics into a reason string
+fn isolate<T>(f: impl FnOnce() -> color_eyre::Result<T>) -> Result<T, String> {
+    ISOLATING.set(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    ISOLATING.set(false);
+
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(format!("{}", e)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Err(format!("panicked: {}", message))
         }
+    }
+}
 
-        self.config = new_config;
-        self.components = components;
-        Ok(())
+/// Keep panics in component code from reaching the terminal-restoring panic hook; they're
+/// logged here and handled by [`isolate`]
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if ISOLATING.get() {
+            logging::log_component_error("PANIC", &format!("{}", info));
+        } else {
+            previous(info);
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolate() {
+        assert_eq!(isolate(|| Ok(1)), Ok(1));
+        assert_eq!(
+            isolate::<()>(|| Err(color_eyre::eyre::eyre!("no battery"))),
+            Err("no battery".to_string())
+        );
+        assert_eq!(
+            isolate::<()>(|| panic!("wpctl missing")),
+            Err("panicked: wpctl missing".to_string())
+        );
+    }
+
+    #[test]
+    fn test_failed_component_is_rebuilt() {
+        let config: Config = serde_json::from_str(
+            r#"{ "bars": { "left": ["broken", "space"], "middle": [], "right": [] }, "colorize": false }"#,
+        )
+        .unwrap();
+        let mut lua_registry = LuaComponentRegistry::new();
+        lua_registry.record_failure("broken", "syntax error near 'end'");
+
+        let components = build_components(&config, &lua_registry);
+        let broken = &components["broken"];
+        assert_eq!(broken.error(), Some("syntax error near 'end'"));
+        assert!(components["space"].error().is_none());
+
+        let Component::Failed(failed) = broken else {
+            panic!("expected a failed component");
+        };
+        assert_eq!(failed.failures, 1);
+        let retried = build_component(&failed.config, &lua_registry, &config, failed.failures);
+        let Component::Failed(retried) = retried else {
+            panic!("expected a failed component");
+        };
+        assert_eq!(retried.failures, 2);
+    }
+
+    #[test]
+    fn test_update_failures_back_off() {
+        let config: Config = serde_json::from_str(
+            r#"{ "bars": { "left": ["flaky"], "middle": [], "right": [] }, "colorize": false }"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("flaky.lua");
+        std::fs::write(
+            &script,
+            r#"return { update = function() error("no battery") end, render = function() return "" end }"#,
+        )
+        .unwrap();
+        let mut lua_registry = LuaComponentRegistry::new();
+        lua_registry
+            .load_component("flaky", script.to_str().unwrap())
+            .unwrap();
+
+        let mut manager = ComponentManager {
+            components: build_components(&config, &lua_registry),
+            config,
+            lua_registry,
+            fingerprints: HashMap::new(),
+            failures: HashMap::new(),
+        };
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            // Fails to update, then is rebuilt on the next pass once due
+            manager.update().unwrap();
+            let Component::Failed(failed) = manager.components.get_mut("flaky").unwrap() else {
+                panic!("expected a failed component");
+            };
+            delays.push((failed.failures, failed.next_update() - Instant::now()));
+            failed.retry_now();
+            manager.update().unwrap();
+            assert!(!matches!(manager.components["flaky"], Component::Failed(_)));
+        }
+
+        assert_eq!(
+            delays
+                .iter()
+                .map(|(failures, _)| *failures)
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(delays[1].1 > std::time::Duration::from_secs(3));
+        assert!(delays[2].1 > std::time::Duration::from_secs(7));
     }
 }
@@ -1,83 +1,233 @@
 use crate::logging;
+use battery::units::{energy::watt_hour, power::watt};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+const DEFAULT_FORMAT: &str = "{icon} {percentage}%";
+
+/// Icons for 0%, 10%, ... 100%
+const DISCHARGING_ICONS: [&str; 11] = ["󰂎", "󰁺", "󰁻", "󰁼", "󰁽", "󰁾", "󰁿", "󰂀", "󰂁", "󰂂", "󰁹"];
+const CHARGING_ICONS: [&str; 11] = ["󰢟", "󰢜", "󰂆", "󰂇", "󰂈", "󰢝", "󰂉", "󰢞", "󰂊", "󰂋", "󰂅"];
+const PLUGGED_ICON: &str = "󰚥";
+const UNKNOWN_ICON: &str = "󰂑";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    /// On AC power and neither charging nor discharging, e.g. full or held at a charge threshold
+    Plugged,
+    #[default]
+    Unknown,
+}
+
+/// One battery's readings, in watt-hours and watts
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BatterySample {
+    pub energy: f32,
+    pub energy_full: f32,
+    pub energy_full_design: f32,
+    pub energy_rate: f32,
+    pub state: Option<battery::State>,
+}
+
+impl From<&battery::Battery> for BatterySample {
+    fn from(battery: &battery::Battery) -> Self {
+        Self {
+            energy: battery.energy().get::<watt_hour>(),
+            energy_full: battery.energy_full().get::<watt_hour>(),
+            energy_full_design: battery.energy_full_design().get::<watt_hour>(),
+            energy_rate: battery.energy_rate().get::<watt>(),
+            state: Some(battery.state()),
+        }
+    }
+}
+
+/// All batteries combined, as if they were a single pack
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatterySummary {
+    pub percentage: u32,
+    pub status: BatteryStatus,
+    /// Until full while charging, until empty while discharging
+    pub time_remaining: Option<Duration>,
+    /// Charge or discharge rate in watts
+    pub power: f32,
+    /// Full capacity relative to design capacity, in percent
+    pub health: Option<u32>,
+}
+
+impl BatterySummary {
+    pub fn aggregate(samples: &[BatterySample], ac_online: bool) -> Self {
+        let sum = |field: fn(&BatterySample) -> f32| samples.iter().map(field).sum::<f32>();
+        let energy = sum(|s| s.energy);
+        let energy_full = sum(|s| s.energy_full);
+        let energy_full_design = sum(|s| s.energy_full_design);
+        let power = sum(|s| s.energy_rate.abs());
+
+        let any = |state: battery::State| samples.iter().any(|s| s.state == Some(state));
+        let all_full = samples
+            .iter()
+            .all(|s| s.state == Some(battery::State::Full));
+        let status = if samples.is_empty() {
+            BatteryStatus::Unknown
+        } else if any(battery::State::Charging) {
+            BatteryStatus::Charging
+        } else if any(battery::State::Discharging) {
+            BatteryStatus::Discharging
+        } else if ac_online || all_full {
+            BatteryStatus::Plugged
+        } else {
+            BatteryStatus::Discharging
+        };
+
+        let hours = match status {
+            BatteryStatus::Charging => Some((energy_full - energy).max(0.0) / power),
+            BatteryStatus::Discharging => Some(energy / power),
+            _ => None,
+        };
+
+        Self {
+            percentage: ratio_percent(energy, energy_full).unwrap_or(0).min(100),
+            status,
+            time_remaining: hours
+                .filter(|h| h.is_finite() && *h > 0.0)
+                .map(|h| Duration::from_secs_f32(h * 3600.0)),
+            power,
+            health: ratio_percent(energy_full, energy_full_design),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        let band = ((self.percentage + 5) / 10).min(10) as usize;
+        match self.status {
+            BatteryStatus::Charging => CHARGING_ICONS[band],
+            BatteryStatus::Discharging => DISCHARGING_ICONS[band],
+            BatteryStatus::Plugged => PLUGGED_ICON,
+            BatteryStatus::Unknown => UNKNOWN_ICON,
+        }
+    }
+
+    /// Fill in `{icon}`, `{percentage}`, `{time}`, `{power}` and `{health}`
+    pub fn format(&self, format: &str) -> String {
+        format
+            .replace("{icon}", self.icon())
+            .replace("{percentage}", &self.percentage.to_string())
+            .replace(
+                "{time}",
+                &self.time_remaining.map(format_duration).unwrap_or_default(),
+            )
+            .replace("{power}", &format!("{:.1}", self.power))
+            .replace(
+                "{health}",
+                &self.health.map(|h| h.to_string()).unwrap_or_default(),
+            )
+    }
+}
+
+fn ratio_percent(value: f32, total: f32) -> Option<u32> {
+    (total > 0.0).then(|| (value / total * 100.0).round() as u32)
+}
+
+/// `2h 05m`, or `45m` under an hour
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Whether a mains or USB-C power supply under `sysfs_root/class/power_supply` is online
+pub fn ac_online(sysfs_root: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(sysfs_root.join("class/power_supply")) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let read =
+            |file: &str| std::fs::read_to_string(entry.path().join(file)).unwrap_or_default();
+        matches!(read("type").trim(), "Mains" | "USB") && read("online").trim() == "1"
+    })
+}
+
 #[derive(Debug)]
 pub struct Battery {
     pub percentage: String,
     pub is_charging: bool,
+    pub summary: BatterySummary,
+    format: String,
     cached_span_content: String,
     battery_manager: battery::Manager,
-    battery: battery::Battery,
     last_update: Instant,
     update_interval: Duration,
 }
 
 impl Battery {
     pub fn new() -> color_eyre::Result<Self> {
-        let manager = battery::Manager::new()?;
-        let battery = match manager.batteries()?.next() {
-            Some(Ok(battery)) => battery,
-            Some(Err(e)) => {
-                logging::log_component_error(
-                    "BATTERY",
-                    &format!("Unable to access battery information: {}", e),
-                );
-                return Err(e.into());
-            }
-            None => {
-                logging::log_component_error("BATTERY", "Unable to find any batteries");
-                return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
-            }
-        };
-
-        let is_charging = matches!(battery.state(), battery::State::Charging);
-        let percentage = ((battery.state_of_charge().value * 100.0) as i32).to_string();
-        let icon = if is_charging { "󰂄" } else { "󰁹" };
-        let cached_span_content = format!("{} {}%", icon, percentage);
+        Self::with_config(None)
+    }
 
-        Ok(Self {
-            percentage,
-            is_charging,
-            cached_span_content,
+    pub fn with_config(format: Option<String>) -> color_eyre::Result<Self> {
+        let manager = battery::Manager::new()?;
+        let mut battery = Self {
+            percentage: String::new(),
+            is_charging: false,
+            summary: BatterySummary::default(),
+            format: format.unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+            cached_span_content: String::new(),
             battery_manager: manager,
-            battery,
             last_update: Instant::now(),
             update_interval: Duration::from_secs(3),
-        })
+        };
+        battery.refresh()?;
+        Ok(battery)
     }
 
     pub fn update(&mut self) -> color_eyre::Result<()> {
-        let now = Instant::now();
-        if now.duration_since(self.last_update) >= self.update_interval {
-            self.battery_manager.refresh(&mut self.battery)?;
-            self.percentage = ((self.battery.state_of_charge().value * 100.0) as i32).to_string();
-            self.is_charging = matches!(self.battery.state(), battery::State::Charging);
-
-            // Update cached span content
-            let icon = if self.is_charging { "󰂄" } else { "󰁹" };
-            self.cached_span_content = format!("{} {}%", icon, self.percentage);
+        if self.last_update.elapsed() >= self.update_interval {
+            self.refresh()?;
+        }
+        Ok(())
+    }
 
-            self.last_update = now;
+    /// Re-read every battery, so packs that are hot-swapped are picked up
+    fn refresh(&mut self) -> color_eyre::Result<()> {
+        let mut samples = Vec::new();
+        for battery in self.battery_manager.batteries()? {
+            match battery {
+                Ok(battery) => samples.push(BatterySample::from(&battery)),
+                Err(e) => logging::log_component_error(
+                    "BATTERY",
+                    &format!("Unable to access battery information: {}", e),
+                ),
+            }
+        }
+        if samples.is_empty() {
+            logging::log_component_error("BATTERY", "Unable to find any batteries");
+            return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
         }
+
+        self.summary = BatterySummary::aggregate(&samples, ac_online(Path::new("/sys")));
+        self.percentage = self.summary.percentage.to_string();
+        self.is_charging = self.summary.status == BatteryStatus::Charging;
+        self.cached_span_content = self.summary.format(&self.format);
+        self.last_update = Instant::now();
         Ok(())
     }
 
+    pub fn next_update(&self) -> Instant {
+        self.last_update + self.update_interval
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
         if colorize {
-            let color = if self.is_charging {
-                Color::Green
-            } else if let Ok(percentage) = self.percentage.parse::<u32>() {
-                if percentage <= 10 {
-                    Color::Red // Very low: Red
-                } else if percentage <= 25 {
-                    Color::Yellow // Low: Yellow/Amber  
-                } else {
-                    Color::Green // Normal/High: Green
-                }
-            } else {
-                Color::Red
+            let color = match self.summary.status {
+                BatteryStatus::Charging | BatteryStatus::Plugged => Color::Green,
+                _ if self.summary.percentage <= 10 => Color::Red, // Very low: Red
+                _ if self.summary.percentage <= 25 => Color::Yellow, // Low: Yellow/Amber
+                _ => Color::Green,                                // Normal/High: Green
             };
             vec![span.fg(color)]
         } else {
@@ -85,3 +235,67 @@ impl Battery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(energy: f32, rate: f32, state: battery::State) -> BatterySample {
+        BatterySample {
+            energy,
+            energy_full: 20.0,
+            energy_full_design: 25.0,
+            energy_rate: rate,
+            state: Some(state),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_two_packs() {
+        // ThinkPads drain the external pack first while the internal one idles
+        let samples = [
+            sample(5.0, 10.0, battery::State::Discharging),
+            sample(15.0, 0.0, battery::State::Unknown),
+        ];
+        let summary = BatterySummary::aggregate(&samples, false);
+
+        assert_eq!(summary.percentage, 50);
+        assert_eq!(summary.status, BatteryStatus::Discharging);
+        assert_eq!(summary.time_remaining, Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(summary.health, Some(80));
+        assert_eq!(summary.icon(), "󰁾");
+        assert_eq!(
+            summary.format("{icon} {percentage}% {time} {power}W {health}%"),
+            "󰁾 50% 2h 00m 10.0W 80%"
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        let charging =
+            BatterySummary::aggregate(&[sample(15.0, 20.0, battery::State::Charging)], true);
+        assert_eq!(charging.status, BatteryStatus::Charging);
+        assert_eq!(charging.time_remaining, Some(Duration::from_secs(15 * 60)));
+        assert_eq!(charging.format("{time}"), "15m");
+
+        // Held below full by a charge threshold
+        let plugged =
+            BatterySummary::aggregate(&[sample(16.0, 0.0, battery::State::Unknown)], true);
+        assert_eq!(plugged.status, BatteryStatus::Plugged);
+        assert_eq!(plugged.icon(), PLUGGED_ICON);
+        assert_eq!(plugged.time_remaining, None);
+    }
+
+    #[test]
+    fn test_ac_online() {
+        let root = tempfile::tempdir().unwrap();
+        let supply = root.path().join("class/power_supply/AC");
+        std::fs::create_dir_all(&supply).unwrap();
+        std::fs::write(supply.join("type"), "Mains\n").unwrap();
+        std::fs::write(supply.join("online"), "0\n").unwrap();
+        assert!(!ac_online(root.path()));
+
+        std::fs::write(supply.join("online"), "1\n").unwrap();
+        assert!(ac_online(root.path()));
+    }
+}
@@ -1,15 +1,107 @@
-use crate::logging;
+use crate::{logging, wakeup};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
-use regex::Regex;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-static BRIGHTNESS_REGEX: std::sync::LazyLock<Regex> =
-    std::sync::LazyLock::new(|| Regex::new(r"\d+%").unwrap());
+const SCREEN_ICON: &str = "󰃠";
+const KEYBOARD_ICON: &str = "󰌌";
+/// `device` value selecting the first keyboard backlight
+const KEYBOARD_DEVICE: &str = "keyboard";
+/// Backlight types in order of preference, as systemd-backlight ranks them
+const BACKLIGHT_TYPES: [&str; 3] = ["firmware", "platform", "raw"];
+/// Not every driver notifies on changes made by hardware keys, so re-read now and then
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(5);
+/// How often to look for a backlight that wasn't there, e.g. an external display coming up
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A screen backlight under `class/backlight`, or a keyboard one under `class/leds`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacklightDevice {
+    pub name: String,
+    pub path: PathBuf,
+    pub keyboard: bool,
+}
+
+impl BacklightDevice {
+    pub fn read_percent(&self) -> Option<u32> {
+        let read = |file: &str| -> Option<u64> {
+            std::fs::read_to_string(self.path.join(file))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        };
+        let max = read("max_brightness").filter(|&max| max > 0)?;
+        Some(((read("brightness")? * 100 + max / 2) / max) as u32)
+    }
+
+    fn icon(&self) -> &'static str {
+        if self.keyboard {
+            KEYBOARD_ICON
+        } else {
+            SCREEN_ICON
+        }
+    }
+}
+
+/// Pick `device` by name (or `"keyboard"`), defaulting to the preferred screen backlight
+pub fn find_device(sysfs_root: &Path, device: Option<&str>) -> Option<BacklightDevice> {
+    let backlights = list_devices(&sysfs_root.join("class/backlight"), false);
+    let keyboards: Vec<_> = list_devices(&sysfs_root.join("class/leds"), true)
+        .into_iter()
+        .filter(|d| d.name.contains("kbd_backlight"))
+        .collect();
+
+    match device {
+        Some(KEYBOARD_DEVICE) => keyboards.into_iter().next(),
+        Some(name) => backlights
+            .into_iter()
+            .chain(keyboards)
+            .find(|d| d.name == name),
+        None => {
+            let rank = |d: &BacklightDevice| {
+                let kind = std::fs::read_to_string(d.path.join("type")).unwrap_or_default();
+                BACKLIGHT_TYPES
+                    .iter()
+                    .position(|t| *t == kind.trim())
+                    .unwrap_or(BACKLIGHT_TYPES.len())
+            };
+            backlights.into_iter().min_by_key(rank)
+        }
+    }
+}
+
+fn list_devices(class_dir: &Path, keyboard: bool) -> Vec<BacklightDevice> {
+    let Ok(entries) = std::fs::read_dir(class_dir) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<_> = entries
+        .flatten()
+        .map(|entry| BacklightDevice {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            path: entry.path(),
+            keyboard,
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
 
 #[derive(Debug)]
 pub struct Brightness {
     pub level: String,
+    /// `None` where there is no backlight, e.g. on desktops; looked for again now and then
+    pub device: Option<BacklightDevice>,
+    sysfs_root: PathBuf,
+    requested: Option<String>,
     cached_span_content: String,
+    /// Set by the watcher when the device's files change
+    changed: Arc<AtomicBool>,
+    last_read: Instant,
+    _watcher: Option<RecommendedWatcher>,
 }
 
 impl Default for Brightness {
@@ -20,20 +112,86 @@ impl Default for Brightness {
 
 impl Brightness {
     pub fn new() -> Self {
-        let level = get_system_brightness().unwrap_or_default();
-        let cached_span_content = format!("󰃠 {}", level);
-        Self {
-            level,
-            cached_span_content,
+        Self::with_config(None)
+    }
+
+    pub fn with_config(device: Option<String>) -> Self {
+        Self::with_sysfs_root(Path::new("/sys"), device.as_deref())
+    }
+
+    pub fn with_sysfs_root(sysfs_root: &Path, device: Option<&str>) -> Self {
+        let mut brightness = Self {
+            level: String::new(),
+            device: None,
+            sysfs_root: sysfs_root.to_path_buf(),
+            requested: device.map(str::to_string),
+            cached_span_content: String::new(),
+            changed: Arc::new(AtomicBool::new(false)),
+            last_read: Instant::now(),
+            _watcher: None,
+        };
+        brightness.find_device();
+        if brightness.device.is_none() {
+            // Expected on desktops, so only mentioned once; the device is looked for again
+            let message = match device {
+                Some(name) => format!("No backlight device named {}", name),
+                None => "No backlight device found".to_string(),
+            };
+            logging::log_component_error("BRIGHTNESS", &message);
         }
+        brightness.read();
+        brightness
+    }
+
+    fn find_device(&mut self) {
+        self.device = find_device(&self.sysfs_root, self.requested.as_deref());
+        self._watcher = self
+            .device
+            .as_ref()
+            .and_then(|device| watch(&device.path, self.changed.clone()));
     }
 
     pub fn update(&mut self) {
-        self.level = get_system_brightness().unwrap_or_default();
-        self.cached_span_content = format!("󰃠 {}", self.level);
+        if self.device.is_none() {
+            if self.last_read.elapsed() >= DEVICE_POLL_INTERVAL {
+                self.find_device();
+                self.read();
+            }
+            return;
+        }
+
+        if self.changed.swap(false, Ordering::Relaxed)
+            || self.last_read.elapsed() >= FALLBACK_INTERVAL
+        {
+            self.read();
+        }
+    }
+
+    fn read(&mut self) {
+        self.last_read = Instant::now();
+        let Some(device) = &self.device else {
+            self.level.clear();
+            self.cached_span_content.clear();
+            return;
+        };
+        let percent = device.read_percent();
+        self.level = percent.map(|p| format!("{}%", p)).unwrap_or_default();
+        self.cached_span_content = format!("{} {}", device.icon(), self.level);
+    }
+
+    pub fn next_update(&self) -> Instant {
+        if self.device.is_none() {
+            self.last_read + DEVICE_POLL_INTERVAL
+        } else {
+            self.last_read + FALLBACK_INTERVAL
+        }
     }
 
+    /// Nothing without a backlight
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
+        if self.device.is_none() {
+            return Vec::new();
+        }
         let span = Span::raw(&self.cached_span_content);
         if colorize {
             vec![span.fg(Color::White)]
@@ -43,30 +201,111 @@ impl Brightness {
     }
 }
 
-fn get_system_brightness() -> Option<String> {
-    let output = Command::new("brightnessctl")
-        .output()
-        .expect("failed to get brightness");
-
-    if output.status.success() {
-        let brightness_str = str::from_utf8(&output.stdout).unwrap();
+/// Flag `changed` whenever a file in the device directory is written
+fn watch(path: &Path, changed: Arc<AtomicBool>) -> Option<RecommendedWatcher> {
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if res.is_ok_and(|event| event.kind.is_modify()) {
+            changed.store(true, Ordering::Relaxed);
+            wakeup::notify();
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            logging::log_component_error("BRIGHTNESS", &format!("Failed to watch: {}", e));
+            return None;
+        }
+    };
+    if let Err(e) = watcher.watch(path, RecursiveMode::NonRecursive) {
+        logging::log_component_error(
+            "BRIGHTNESS",
+            &format!("Failed to watch {}: {}", path.display(), e),
+        );
+        return None;
+    }
+    Some(watcher)
+}
 
-        let re = &BRIGHTNESS_REGEX;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if let Some(brightness) = re.find(brightness_str).map(|m| m.as_str()) {
-            return Some(brightness.to_string());
+    fn add_device(root: &Path, class: &str, name: &str, kind: &str, brightness: u32, max: u32) {
+        let dir = root.join("class").join(class).join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("brightness"), format!("{}\n", brightness)).unwrap();
+        std::fs::write(dir.join("max_brightness"), format!("{}\n", max)).unwrap();
+        if !kind.is_empty() {
+            std::fs::write(dir.join("type"), format!("{}\n", kind)).unwrap();
         }
+    }
 
-        logging::log_component_error(
-            "BRIGHTNESS",
-            &format!("Failed to parse brightness from output: {}", brightness_str),
+    fn fixture() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        add_device(root.path(), "backlight", "acpi_video0", "firmware", 5, 10);
+        add_device(
+            root.path(),
+            "backlight",
+            "intel_backlight",
+            "raw",
+            19200,
+            96000,
         );
-    } else {
-        logging::log_component_error(
-            "BRIGHTNESS",
-            str::from_utf8(&output.stderr).unwrap_or("unknown error"),
+        add_device(root.path(), "leds", "input3::capslock", "", 0, 1);
+        add_device(root.path(), "leds", "tpacpi::kbd_backlight", "", 1, 2);
+        root
+    }
+
+    #[test]
+    fn test_find_device() {
+        let root = fixture();
+        let find = |device| find_device(root.path(), device).map(|d| d.name);
+
+        assert_eq!(find(None).as_deref(), Some("acpi_video0"));
+        assert_eq!(
+            find(Some("intel_backlight")).as_deref(),
+            Some("intel_backlight")
+        );
+        assert_eq!(
+            find(Some("keyboard")).as_deref(),
+            Some("tpacpi::kbd_backlight")
         );
+        assert_eq!(find(Some("missing")), None);
+
+        let intel = find_device(root.path(), Some("intel_backlight")).unwrap();
+        assert_eq!(intel.read_percent(), Some(20));
+    }
+
+    #[test]
+    fn test_missing_device() {
+        let root = tempfile::tempdir().unwrap();
+        let mut brightness = Brightness::with_sysfs_root(root.path(), None);
+        assert!(brightness.render_as_spans(true).is_empty());
+        assert!(brightness.next_update() >= Instant::now() + FALLBACK_INTERVAL);
+
+        add_device(root.path(), "backlight", "intel_backlight", "raw", 3, 10);
+        brightness.last_read -= DEVICE_POLL_INTERVAL;
+        brightness.update();
+        assert_eq!(brightness.cached_span_content, "󰃠 30%");
     }
 
-    None
+    #[test]
+    fn test_watch_for_changes() {
+        let root = fixture();
+        let mut brightness = Brightness::with_sysfs_root(root.path(), Some("keyboard"));
+        assert_eq!(brightness.cached_span_content, "󰌌 50%");
+
+        std::fs::write(
+            root.path()
+                .join("class/leds/tpacpi::kbd_backlight/brightness"),
+            "2\n",
+        )
+        .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !brightness.changed.load(Ordering::Relaxed) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        brightness.update();
+        assert_eq!(brightness.level, "100%");
+    }
 }
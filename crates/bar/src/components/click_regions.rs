@@ -0,0 +1,95 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    text::Span,
+};
+
+/// Columns covered by one rendered span, used to route mouse clicks back to its component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickRegion {
+    /// First column of the span
+    pub start: u16,
+    /// Column just past the span
+    pub end: u16,
+    /// Name of the component, as used in the bar configuration
+    pub component: String,
+    /// Index of the span among the component's rendered spans
+    pub span: usize,
+}
+
+impl ClickRegion {
+    pub fn contains(&self, column: u16) -> bool {
+        (self.start..self.end).contains(&column)
+    }
+}
+
+/// Lay out each component's spans as a single aligned line within `area`, as the
+/// bars' `Paragraph` does, and record where every span ends up
+pub fn click_regions(
+    area: Rect,
+    alignment: Alignment,
+    components: &[(&str, Vec<Span>)],
+) -> Vec<ClickRegion> {
+    let line_width: usize = components
+        .iter()
+        .flat_map(|(_, spans)| spans)
+        .map(Span::width)
+        .sum();
+    let free = (area.width as usize).saturating_sub(line_width);
+    let offset = match alignment {
+        Alignment::Left => 0,
+        Alignment::Center => free / 2,
+        Alignment::Right => free,
+    };
+
+    let area_end = area.x as usize + area.width as usize;
+    let mut column = area.x as usize + offset;
+    let mut regions = Vec::new();
+    for (name, spans) in components {
+        for (index, span) in spans.iter().enumerate() {
+            let start = column;
+            column += span.width();
+            if start >= area_end {
+                return regions;
+            }
+            regions.push(ClickRegion {
+                start: start as u16,
+                end: column.min(area_end) as u16,
+                component: name.to_string(),
+                span: index,
+            });
+        }
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_regions() {
+        let area = Rect::new(10, 0, 20, 1);
+        let components = vec![
+            ("workspaces", vec![Span::raw(" 1 "), Span::raw(" 2 ")]),
+            ("kitty_tabs", vec![Span::raw(" 󰆍 zsh ")]),
+        ];
+
+        let regions = click_regions(area, Alignment::Left, &components);
+        assert_eq!(
+            regions
+                .iter()
+                .map(|r| (r.start, r.end, r.span))
+                .collect::<Vec<_>>(),
+            vec![(10, 13, 0), (13, 16, 1), (16, 23, 0)]
+        );
+        assert_eq!(regions[2].component, "kitty_tabs");
+        assert!(regions[2].contains(22));
+        assert!(!regions[2].contains(23));
+
+        let regions = click_regions(area, Alignment::Right, &components);
+        assert_eq!((regions[0].start, regions[2].end), (17, 30));
+
+        let regions = click_regions(area, Alignment::Center, &components);
+        assert_eq!(regions[0].start, 13);
+    }
+}
@@ -1,13 +1,13 @@
 use super::sparkline::Sparkline;
+use crate::provider::{Subscription, SystemSnapshot, system};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
 use std::time::{Duration, Instant};
-use sysinfo::{CpuRefreshKind, RefreshKind, System};
 
 #[derive(Debug)]
 pub struct Cpu {
     pub usage: String,
     cached_span_content: String,
-    system: System,
+    system: Subscription<SystemSnapshot>,
     last_update: Instant,
     update_interval: Duration,
     sparkline: Sparkline,
@@ -20,10 +20,6 @@ impl Cpu {
         sparkline_update_freq: u64,
         sparkline_logarithmic: bool,
     ) -> Self {
-        let system = System::new_with_specifics(
-            RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
-        );
-
         let usage = "0".to_string();
         let sparkline = Sparkline::new(sparkline, sparkline_length, sparkline_logarithmic);
         let cached_span_content = if sparkline.enabled {
@@ -35,7 +31,7 @@ impl Cpu {
         Self {
             usage,
             cached_span_content,
-            system,
+            system: system::subscribe(),
             last_update: Instant::now(),
             update_interval: Duration::from_secs(sparkline_update_freq),
             sparkline,
@@ -45,12 +41,7 @@ impl Cpu {
     pub fn update(&mut self) {
         let now = Instant::now();
         if now.duration_since(self.last_update) >= self.update_interval {
-            self.system.refresh_cpu_all();
-
-            let iter = self.system.cpus().iter();
-            let count = iter.len() as f32;
-            let sum = iter.fold(0.0, |acc, x| acc + x.cpu_usage());
-            let avg: u32 = (sum / count) as u32;
+            let avg = self.system.snapshot().cpu_usage as u32;
             self.usage = avg.to_string();
 
             if self.sparkline.enabled {
@@ -67,6 +58,10 @@ impl Cpu {
         }
     }
 
+    pub fn next_update(&self) -> Instant {
+        self.last_update + self.update_interval
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
         if colorize {
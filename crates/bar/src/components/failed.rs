@@ -0,0 +1,67 @@
+use crate::config::ComponentConfig;
+use ratatui::{prelude::Stylize, style::Color, text::Span};
+use std::time::{Duration, Instant};
+
+const FAILED_ICON: &str = "  ";
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// A component that failed to start or update, waiting to be rebuilt
+#[derive(Debug, Clone)]
+pub struct Failed {
+    pub config: ComponentConfig,
+    pub reason: String,
+    /// Consecutive failures, which double the delay before the next attempt
+    pub failures: u32,
+    retry_at: Instant,
+}
+
+impl Failed {
+    pub fn new(config: ComponentConfig, reason: String, failures: u32) -> Self {
+        Self {
+            config,
+            reason,
+            failures,
+            retry_at: Instant::now() + retry_delay(failures),
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        Instant::now() >= self.retry_at
+    }
+
+    /// Retry on the next update instead of waiting out the backoff
+    pub fn retry_now(&mut self) {
+        self.retry_at = Instant::now();
+    }
+
+    pub fn next_update(&self) -> Instant {
+        self.retry_at
+    }
+
+    pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
+        if colorize {
+            vec![Span::raw(FAILED_ICON).fg(Color::Yellow)]
+        } else {
+            vec![Span::raw(FAILED_ICON)]
+        }
+    }
+}
+
+fn retry_delay(failures: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+}
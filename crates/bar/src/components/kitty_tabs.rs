@@ -1,38 +1,44 @@
 use ratatui::{prelude::Stylize, style::Color, text::Span};
-use serde::Deserialize;
-use std::process::Command;
 
-use crate::logging;
+use crate::kitty::{KittyClient, SocketDiscovery};
+use crate::provider::{self, Publisher, Subscription};
+use crate::window_manager::WindowManager;
+use crate::{apps, logging, window_manager};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
 
-#[derive(Deserialize, Debug)]
-struct KittyWindow {
-    #[serde(default)]
-    is_active: bool,
-    #[serde(default)]
-    is_focused: bool,
-    #[serde(default)]
-    last_focused: bool,
-    tabs: Vec<KittyTab>,
-}
+/// Shell/terminal icon and kitty gray for tabs no rule matches
+const DEFAULT_TAB_ICON: &str = "󰆍";
+const DEFAULT_TAB_COLORS: (Color, Color) = (Color::Rgb(103, 117, 140), Color::White);
 
-#[derive(Deserialize, Debug)]
-struct KittyTab {
-    title: String,
-    #[serde(default)]
-    is_active: bool,
-}
+/// How often tabs are re-read from the focused kitty instance
+const TAB_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long to wait before looking for the same kitty's socket again after not finding it
+const DISCOVERY_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+const BELL_INDICATOR: &str = "󰂞";
+const RUNNING_INDICATOR: &str = "󰑮";
+const ACTIVITY_INDICATOR: &str = "•";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct TabInfo {
+    id: u64,
     title: String,
     is_active: bool,
+    /// Foreground program of the tab's active window, e.g. `nvim src/main.rs`
+    command: Option<String>,
+    bell: bool,
+    activity: bool,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default)]
 pub struct KittyTabs {
     pub tabs: Vec<TabInfo>,
-    kitty_pid: Option<u32>,
-    socket_path: Option<String>,
+    /// Tabs published by the background poller; `None` in tests, which then leave the host's
+    /// kitty alone
+    kitty: Option<Subscription<Vec<TabInfo>>>,
+    /// Ids of clicked tabs, focused by the poller
+    clicks: Option<Sender<u64>>,
 }
 
 impl KittyTabs {
@@ -40,21 +46,24 @@ impl KittyTabs {
         Self::with_config(None)
     }
 
+    /// `socket_path` is a socket path, or a template containing `{pid}`; discovered when unset
     pub fn with_config(socket_path: Option<String>) -> Self {
-        Self {
-            tabs: get_kitty_tabs(socket_path.as_deref()).unwrap_or_default(),
-            kitty_pid: get_focused_kitty_pid(),
-            socket_path,
-        }
+        let (clicks, clicked) = mpsc::channel();
+        let mut kitty_tabs = Self {
+            kitty: Some(provider::spawn(move |publisher| {
+                poll_tabs(publisher, socket_path, clicked)
+            })),
+            clicks: Some(clicks),
+            ..Self::default()
+        };
+        kitty_tabs.update();
+        kitty_tabs
     }
 
     pub fn update(&mut self) {
-        self.kitty_pid = get_focused_kitty_pid();
-        self.tabs = if let Some(pid) = self.kitty_pid {
-            get_kitty_tabs_for_pid(pid, self.socket_path.as_deref()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
+        if let Some(kitty) = &self.kitty {
+            self.tabs = kitty.snapshot();
+        }
     }
 
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
@@ -67,18 +76,17 @@ impl KittyTabs {
             .map(|tab| {
                 if tab.is_active {
                     // Active tab: show icon + full title
-                    let icon = get_tab_icon(&tab.title);
                     let title = truncate_title(&tab.title, true);
-                    let content = format!(" {} {} ", icon, title);
+                    let content = format!(" {} {}{} ", tab_icon(tab), title, indicators(tab));
                     if colorize {
-                        let (bg_color, fg_color) = get_tab_color(&tab.title);
+                        let (bg_color, fg_color) = tab_color(tab);
                         Span::raw(content).bg(bg_color).fg(fg_color)
                     } else {
                         Span::raw(content).bg(Color::White).fg(Color::Black)
                     }
                 } else {
                     // Inactive tab: show icon only (very compact)
-                    let content = format!(" {} ", get_tab_icon(&tab.title));
+                    let content = format!(" {}{} ", tab_icon(tab), indicators(tab));
                     if colorize {
                         Span::raw(content).fg(Color::Rgb(103, 117, 140)) // Kitty gray text
                     } else {
@@ -88,369 +96,192 @@ impl KittyTabs {
             })
             .collect::<Vec<Span>>()
     }
-}
 
-fn truncate_title(title: &str, is_active: bool) -> String {
-    // NOTE: This might be redundant given that we always get_tab_icon for non-active tabs below.
-    // TODO: Make these truncation lengths configurable constants instead of magic numbers
-    // Allow users to customize max title lengths via configuration
-    let max_len = if is_active { 16 } else { 12 }; // Account for icon in active tabs
-    if title.len() > max_len {
-        format!("{}...", &title[..max_len - 3])
-    } else {
-        title.to_string()
+    /// Focus the clicked tab; spans map one-to-one to tabs
+    pub fn on_click(&mut self, span: usize) {
+        if let (Some(tab), Some(clicks)) = (self.tabs.get(span), &self.clicks) {
+            let _ = clicks.send(tab.id);
+        }
     }
 }
 
-// TODO: Refactor this large match statement to use a data-driven approach
-// Consider extracting to a HashMap lookup or creating separate modules by category
-// This would improve maintainability and make adding new applications easier
-fn get_tab_color(title: &str) -> (Color, Color) {
-    let title_lower = title.to_lowercase();
-
-    // Terminal-based applications - use title
-    if title_lower.starts_with("nvim") || title_lower.contains("neovim") {
-        return (Color::Rgb(0, 107, 84), Color::White); // Neovim Green
-    } else if title_lower.starts_with("vim") {
-        return (Color::Rgb(19, 134, 71), Color::White); // Vim Green
-    } else if title_lower.starts_with("emacs") {
-        return (Color::Rgb(146, 35, 127), Color::White); // Emacs Purple
-    } else if title_lower.starts_with("htop") || title_lower.starts_with("btop") {
-        return (Color::Rgb(255, 152, 0), Color::Black); // System Monitor Orange
-    } else if title_lower.starts_with("yazi") {
-        return (Color::Rgb(255, 200, 87), Color::Black); // Yazi Yellow
-    } else if title_lower.starts_with("ranger") || title_lower.starts_with("lf") {
-        return (Color::Rgb(41, 128, 185), Color::White); // File Manager Blue
-    } else if title_lower.starts_with("git") {
-        return (Color::Rgb(240, 80, 50), Color::White); // Git Orange
-    } else if title_lower.starts_with("ssh") {
-        return (Color::Rgb(0, 100, 200), Color::White); // SSH Blue
-    } else if title_lower.starts_with("cmus") || title_lower.starts_with("ncmpcpp") {
-        return (Color::Rgb(29, 185, 84), Color::White); // Music Green
-    } else if title_lower.starts_with("docker") {
-        return (Color::Rgb(41, 128, 185), Color::White); // Docker Blue
-    } else if title_lower.starts_with("node") || title_lower.starts_with("npm") {
-        return (Color::Rgb(102, 77, 255), Color::White); // Node.js Green
-    } else if title_lower.starts_with("python") || title_lower.starts_with("python3") {
-        return (Color::Rgb(53, 114, 165), Color::White); // Python Blue
-    } else if title_lower.starts_with("rustc") || title_lower.contains("cargo") {
-        return (Color::Rgb(222, 76, 65), Color::White); // Rust Orange
-    } else if title_lower.starts_with("go") {
-        return (Color::Rgb(0, 173, 216), Color::Black); // Go Cyan
-    } else if title_lower.starts_with("java") {
-        return (Color::Rgb(255, 87, 34), Color::White); // Java Orange
-    } else if title_lower.starts_with("k9s") || title_lower.starts_with("kubectl") {
-        return (Color::Rgb(61, 90, 254), Color::White); // Kubernetes Blue
-    } else if title_lower.starts_with("terraform") || title_lower.starts_with("tf") {
-        return (Color::Rgb(94, 103, 110), Color::White); // Terraform Gray
-    } else if title_lower.starts_with("lazygit") || title_lower.starts_with("gitui") {
-        return (Color::Rgb(240, 80, 50), Color::White); // Git UI Orange
-    } else if title_lower.starts_with("tmux") || title_lower.starts_with("screen") {
-        return (Color::Rgb(46, 52, 64), Color::White); // Terminal Multiplexer Dark
-    } else if title_lower.starts_with("weechat") || title_lower.starts_with("irssi") {
-        return (Color::Rgb(254, 0, 84), Color::White); // IRC Red
-    } else if title_lower.starts_with("neomutt") || title_lower.starts_with("mutt") {
-        return (Color::Rgb(0, 112, 193), Color::White); // Email Blue
-    } else if title_lower.starts_with("newsboat") || title_lower.starts_with("nnn") {
-        return (Color::Rgb(255, 193, 7), Color::Black); // News Yellow
-    } else if title_lower.starts_with("glow") || title_lower.starts_with("mdcat") {
-        return (Color::Rgb(33, 150, 243), Color::White); // Markdown Blue
-    } else if title_lower.starts_with("tig") || title_lower.starts_with("lazydocker") {
-        return (Color::Rgb(240, 80, 50), Color::White); // Git TUI Orange
-    } else if title_lower.starts_with("jq") || title_lower.starts_with("yq") {
-        return (Color::Rgb(0, 150, 136), Color::White); // JSON/YAML Teal
-    } else if title_lower.starts_with("sqlite3")
-        || title_lower.starts_with("mysql")
-        || title_lower.starts_with("redis-cli")
-        || title_lower.starts_with("psql")
-    {
-        return (Color::Rgb(40, 167, 69), Color::White); // Database Green
-    } else if title_lower.starts_with("gh") || title_lower.starts_with("hub") {
-        return (Color::Rgb(29, 185, 84), Color::White); // GitHub Green
-    } else if title_lower.starts_with("opencode")
-        || title_lower.contains("opencode")
-        || title_lower.starts_with("oc |")
-    {
-        return (Color::Rgb(88, 101, 242), Color::White); // OpenCode Blue
-    } else if title_lower.contains("watch") || title_lower.contains("tail") {
-        return (Color::Rgb(156, 39, 176), Color::White); // Watch Purple
-    } else if title_lower.starts_with("wget") || title_lower.starts_with("curl") {
-        return (Color::Rgb(52, 152, 219), Color::White); // Network Blue
-    } else if title_lower.starts_with("make") || title_lower.starts_with("cmake") {
-        return (Color::Rgb(230, 126, 34), Color::White); // Build Orange
-    } else if title_lower.starts_with("gdb") || title_lower.starts_with("lldb") {
-        return (Color::Rgb(231, 76, 60), Color::White); // Debugger Red
-    } else if title_lower.starts_with("hugo") || title_lower.starts_with("jekyll") {
-        return (Color::Rgb(155, 89, 182), Color::White); // SSG Purple
-    } else if title_lower.starts_with("pip") || title_lower.starts_with("poetry") {
-        return (Color::Rgb(53, 114, 165), Color::White); // Python Package Blue
-    } else if title_lower.starts_with("deno") || title_lower.starts_with("bun") {
-        return (Color::Rgb(46, 125, 50), Color::White); // JS Runtime Green
-    } else if title_lower.starts_with("zig") || title_lower.starts_with("nim") {
-        return (Color::Rgb(222, 76, 65), Color::White); // Compiled Lang Orange
-    }
-
-    // Default fallback for shells and other terminals
-    (Color::Rgb(103, 117, 140), Color::White) // Kitty Gray
+/// The focused kitty instance, followed by the poller
+#[derive(Debug, Default)]
+struct Connection {
+    kitty_pid: Option<u32>,
+    /// Socket path, or a template containing `{pid}`; discovered when unset
+    socket_path: Option<String>,
+    /// Replaced when focus moves to another instance
+    client: Option<KittyClient>,
+    /// When to look for the focused kitty's socket again, after failing to find it
+    discovery_retry_at: Option<Instant>,
 }
 
-// TODO: Refactor this large match statement to use a HashMap or macro approach
-// Consider separating into different modules by category (editors, tools, etc.)
-// This 130+ line function is difficult to maintain and extend
-fn get_tab_icon(title: &str) -> String {
-    let title_lower = title.to_lowercase();
-
-    // Check for common terminal programs first
-    if title_lower.starts_with("nvim") || title_lower.contains("neovim") {
-        return "".to_string();
-    } else if title_lower.starts_with("vim") {
-        return "".to_string();
-    } else if title_lower.starts_with("emacs") {
-        return "󰍹".to_string();
-    } else if title_lower.starts_with("nano") {
-        return "".to_string();
-    } else if title_lower.starts_with("htop") || title_lower.starts_with("btop") {
-        return "󰔚".to_string();
-    } else if title_lower.starts_with("yazi") {
-        return "󰇥".to_string();
-    } else if title_lower.starts_with("ranger") || title_lower.starts_with("lf") {
-        return "󰉋".to_string();
-    } else if title_lower.starts_with("git") {
-        return "󰊢".to_string();
-    } else if title_lower.starts_with("man") {
-        return "󰍹".to_string();
-    } else if title_lower.starts_with("ssh") {
-        return "󰣀".to_string();
-    } else if title_lower.starts_with("cmus") || title_lower.starts_with("ncmpcpp") {
-        return "󰓇".to_string();
-    } else if title_lower.starts_with("docker") {
-        return "󰡨".to_string();
-    } else if title_lower.starts_with("node") || title_lower.starts_with("npm") {
-        return "󰎙".to_string();
-    } else if title_lower.starts_with("python") || title_lower.starts_with("python3") {
-        return "󰌠".to_string();
-    } else if title_lower.starts_with("rustc") || title_lower.contains("cargo") {
-        return "󱘗".to_string();
-    } else if title_lower.starts_with("go") {
-        return "󰟦".to_string();
-    } else if title_lower.starts_with("java") {
-        return "󰬙".to_string();
-    } else if title_lower.starts_with("fish")
-        || title_lower.starts_with("bash")
-        || title_lower.starts_with("zsh")
-    {
-        return "󰆍".to_string();
-    } else if title_lower.contains("watch") || title_lower.contains("tail") {
-        return "󰈰".to_string();
-    } else if title_lower.starts_with("wget") || title_lower.starts_with("curl") {
-        return "󰈁".to_string();
-    } else if title_lower.contains("edit") || title_lower.contains("vi") {
-        return "".to_string();
-    } else if title_lower.starts_with("opencode")
-        || title_lower.contains("opencode")
-        || title_lower.starts_with("oc |")
-    {
-        return "󰚩".to_string();
-    } else if title_lower.starts_with("lazygit") || title_lower.starts_with("gitui") {
-        return "󰊢".to_string();
-    } else if title_lower.starts_with("bat")
-        || title_lower.starts_with("less")
-        || title_lower.starts_with("more")
-    {
-        return "󰈚".to_string();
-    } else if title_lower.starts_with("exa")
-        || title_lower.starts_with("lsd")
-        || title_lower.starts_with("tree")
-    {
-        return "󰉋".to_string();
-    } else if title_lower.starts_with("fd")
-        || title_lower.starts_with("find")
-        || title_lower.starts_with("rg")
-        || title_lower.starts_with("grep")
-        || title_lower.starts_with("ag")
-    {
-        return "󰍉".to_string();
-    } else if title_lower.starts_with("k9s") || title_lower.starts_with("kubectl") {
-        return "󱃾".to_string();
-    } else if title_lower.starts_with("terraform") || title_lower.starts_with("tf") {
-        return "󱁢".to_string();
-    } else if title_lower.starts_with("ansible") || title_lower.starts_with("ansible-playbook") {
-        return "󰔚".to_string();
-    } else if title_lower.starts_with("tmux") || title_lower.starts_with("screen") {
-        return "󰆍".to_string();
-    } else if title_lower.starts_with("weechat") || title_lower.starts_with("irssi") {
-        return "󰒱".to_string();
-    } else if title_lower.starts_with("neomutt") || title_lower.starts_with("mutt") {
-        return "󰇰".to_string();
-    } else if title_lower.starts_with("newsboat") || title_lower.starts_with("nnn") {
-        return "󰎕".to_string();
-    } else if title_lower.starts_with("ncdu") || title_lower.starts_with("du") {
-        return "󰉋".to_string();
-    } else if title_lower.starts_with("glow") || title_lower.starts_with("mdcat") {
-        return "󰍹".to_string();
-    } else if title_lower.starts_with("tig") || title_lower.starts_with("lazydocker") {
-        return "󰊢".to_string();
-    } else if title_lower.starts_with("fzf")
-        || title_lower.starts_with("peco")
-        || title_lower.starts_with("ripgrep-all")
-        || title_lower.starts_with("rga")
-    {
-        return "󰍉".to_string();
-    } else if title_lower.starts_with("jq") || title_lower.starts_with("yq") {
-        return "󰉼".to_string();
-    } else if title_lower.starts_with("bottom") || title_lower.starts_with("glances") {
-        return "󰔚".to_string();
-    } else if title_lower.starts_with("nmap") || title_lower.starts_with("netstat") {
-        return "󰈁".to_string();
-    } else if title_lower.starts_with("hugo") || title_lower.starts_with("jekyll") {
-        return "󰀶".to_string();
-    } else if title_lower.starts_with("pip") || title_lower.starts_with("poetry") {
-        return "󰌠".to_string();
-    } else if title_lower.starts_with("deno") || title_lower.starts_with("bun") {
-        return "󰎙".to_string();
-    } else if title_lower.starts_with("zig") || title_lower.starts_with("nim") {
-        return "󱘗".to_string();
-    } else if title_lower.starts_with("make") || title_lower.starts_with("cmake") {
-        return "󰔧".to_string();
-    } else if title_lower.starts_with("gdb") || title_lower.starts_with("lldb") {
-        return "󰃤".to_string();
-    } else if title_lower.starts_with("strace")
-        || title_lower.starts_with("ltrace")
-        || title_lower.starts_with("valgrind")
-    {
-        return "󰔚".to_string();
-    } else if title_lower.starts_with("wireshark") || title_lower.starts_with("tshark") {
-        return "󰈁".to_string();
-    } else if title_lower.starts_with("sqlite3")
-        || title_lower.starts_with("mysql")
-        || title_lower.starts_with("redis-cli")
-        || title_lower.starts_with("psql")
-    {
-        return "󰆼".to_string();
-    } else if title_lower.starts_with("gh") || title_lower.starts_with("hub") {
-        return "󰊢".to_string();
-    } else if title_lower.starts_with("alacritty") || title_lower.starts_with("foot") {
-        return "󰆍".to_string();
-    } else if title_lower.starts_with("nvim-qt") || title_lower.starts_with("gvim") {
-        return "".to_string();
-    }
+impl Connection {
+    /// Find the socket of the kitty with `pid` when focus moves to it; discovery scans `/proc`
+    /// and socket directories, so a kitty without one is only rescanned after a while
+    fn connect(&mut self, pid: Option<u32>, now: Instant) {
+        let retry_due = self.discovery_retry_at.is_some_and(|at| now >= at);
+        if pid == self.kitty_pid && !retry_due {
+            return;
+        }
 
-    // Default shell/terminal icon
-    "󰆍".to_string()
+        self.kitty_pid = pid;
+        self.client = pid
+            .and_then(|pid| SocketDiscovery::new(self.socket_path.clone()).find(pid))
+            .map(KittyClient::new);
+        self.discovery_retry_at =
+            (pid.is_some() && self.client.is_none()).then(|| now + DISCOVERY_RETRY_INTERVAL);
+    }
 }
 
-// TODO: Consider caching the Kitty PID for short periods to reduce hyprctl calls
-// This would improve performance by avoiding repeated system calls during update cycles
-fn get_focused_kitty_pid() -> Option<u32> {
-    let active_output = Command::new("hyprctl")
-        .args(["activewindow", "-j"])
-        .output()
-        .ok()?;
-
-    if !active_output.status.success() {
-        return None;
-    }
+/// Kitty doesn't announce tab changes, so its tabs are polled on a background thread, where a
+/// slow instance can't hold up rendering; clicks are handled there too
+fn poll_tabs(
+    publisher: Publisher<Vec<TabInfo>>,
+    socket_path: Option<String>,
+    clicked: Receiver<u64>,
+) {
+    let window_manager = window_manager::subscribe();
+    let mut connection = Connection {
+        socket_path,
+        ..Connection::default()
+    };
+    loop {
+        let pid = get_focused_kitty_pid(window_manager.as_deref());
+        connection.connect(pid, Instant::now());
+        let tabs = connection
+            .client
+            .as_mut()
+            .and_then(get_kitty_tabs)
+            .unwrap_or_default();
+        if !publisher.publish(tabs) {
+            return;
+        }
 
-    let active_stdout = String::from_utf8(active_output.stdout).ok()?;
-    if let Ok(active_window) = serde_json::from_str::<serde_json::Value>(&active_stdout)
-        && let (Some(class), Some(pid)) = (
-            active_window.get("class").and_then(|v| v.as_str()),
-            active_window.get("pid").and_then(|v| v.as_u64()),
-        )
-    {
-        // Only return if the active window is a Kitty window
-        if class == "kitty" {
-            // Check if this Kitty instance uses --single-instance flag
-            if is_single_instance_kitty(pid as u32) {
-                return Some(pid as u32);
+        match clicked.recv_timeout(TAB_POLL_INTERVAL) {
+            Ok(id) => {
+                if let Some(Err(e)) = connection.client.as_mut().map(|c| c.focus_tab(id)) {
+                    logging::log_component_error("KITTY_TABS", &format!("{}", e));
+                }
             }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
         }
     }
-
-    None
 }
 
-fn is_single_instance_kitty(pid: u32) -> bool {
-    let output = match Command::new("pgrep")
-        .args(["-f", "kitty.*--single-instance"])
-        .output()
-    {
-        Ok(output) => output,
-        Err(_) => return false,
-    };
-
-    if !output.status.success() {
-        return false;
+/// Bell, plus activity and running-program markers for tabs that aren't in view
+fn indicators(tab: &TabInfo) -> String {
+    let mut indicators = String::new();
+    if tab.bell {
+        indicators.push(' ');
+        indicators.push_str(BELL_INDICATOR);
     }
-
-    let stdout = match String::from_utf8(output.stdout) {
-        Ok(stdout) => stdout,
-        Err(_) => return false,
-    };
-    for pid_str in stdout.lines() {
-        if let Ok(found_pid) = pid_str.parse::<u32>()
-            && found_pid == pid
-        {
-            return true;
-        }
+    if !tab.is_active && tab.command.is_some() {
+        indicators.push(' ');
+        indicators.push_str(RUNNING_INDICATOR);
+    }
+    if !tab.is_active && tab.activity {
+        indicators.push(' ');
+        indicators.push_str(ACTIVITY_INDICATOR);
     }
+    indicators
+}
+
+/// Icon for the tab's foreground program, falling back to its title
+fn tab_icon(tab: &TabInfo) -> String {
+    tab.command
+        .as_deref()
+        .and_then(|command| apps::registry().lookup(None, command).icon)
+        .unwrap_or_else(|| get_tab_icon(&tab.title))
+}
 
-    false
+fn tab_color(tab: &TabInfo) -> (Color, Color) {
+    tab.command
+        .as_deref()
+        .and_then(|command| apps::registry().lookup(None, command).colors)
+        .unwrap_or_else(|| get_tab_color(&tab.title))
 }
 
-// TODO: This function appears redundant - consider removing and using get_kitty_tabs_for_pid directly
-// The socket_path parameter makes this wrapper unnecessary
-fn get_kitty_tabs(socket_path: Option<&str>) -> Option<Vec<TabInfo>> {
-    if let Some(pid) = get_focused_kitty_pid() {
-        get_kitty_tabs_for_pid(pid, socket_path)
+fn truncate_title(title: &str, is_active: bool) -> String {
+    // NOTE: This might be redundant given that we always get_tab_icon for non-active tabs below.
+    // TODO: Make these truncation lengths configurable constants instead of magic numbers
+    // Allow users to customize max title lengths via configuration
+    let max_len = if is_active { 16 } else { 12 }; // Account for icon in active tabs
+    if title.chars().count() > max_len {
+        let truncated: String = title.chars().take(max_len - 3).collect();
+        format!("{}...", truncated)
     } else {
-        None
+        title.to_string()
     }
 }
 
-fn get_kitty_tabs_for_pid(pid: u32, socket_path: Option<&str>) -> Option<Vec<TabInfo>> {
-    let socket_path_str = match socket_path {
-        Some(path) => path.to_string(),
-        None => format!("/tmp/kitty-{}", pid),
-    };
+fn get_tab_color(title: &str) -> (Color, Color) {
+    apps::registry()
+        .lookup(None, title)
+        .colors
+        .unwrap_or(DEFAULT_TAB_COLORS)
+}
+
+fn get_tab_icon(title: &str) -> String {
+    apps::registry()
+        .lookup(None, title)
+        .icon
+        .unwrap_or_else(|| DEFAULT_TAB_ICON.to_string())
+}
 
-    let output = Command::new("kitty")
-        .args(["@", "--to", &format!("unix:{}", socket_path_str), "ls"])
-        .output()
-        .ok()?;
+fn get_focused_kitty_pid(window_manager: Option<&dyn WindowManager>) -> Option<u32> {
+    let state = window_manager?.state();
+    let active_window = state.focused_window()?;
 
-    if !output.status.success() {
-        logging::log_component_error(
-            "KITTY_TABS",
-            str::from_utf8(&output.stderr).unwrap_or("unknown error"),
-        );
-        return None;
+    // Only return if the active window is a Kitty window
+    if active_window.class == "kitty" {
+        return active_window.pid;
     }
 
-    let stdout = str::from_utf8(&output.stdout).unwrap();
-    let windows: Vec<KittyWindow> = serde_json::from_str(stdout).ok()?;
-
-    for window in windows {
-        if window.is_active || window.is_focused || window.last_focused {
-            let tabs: Vec<TabInfo> = window
-                .tabs
-                .into_iter()
-                .enumerate()
-                .map(|(index, tab)| TabInfo {
-                    title: if tab.title.is_empty() {
-                        format!("Tab {}", index + 1)
-                    } else {
-                        tab.title
-                    },
-                    is_active: tab.is_active,
-                })
-                .collect();
+    None
+}
 
-            return Some(tabs);
+/// Tabs of the focused OS window
+fn get_kitty_tabs(client: &mut KittyClient) -> Option<Vec<TabInfo>> {
+    let windows = match client.ls() {
+        Ok(windows) => windows,
+        Err(e) => {
+            // No socket simply means this kitty isn't listening for remote control
+            if e.downcast_ref::<std::io::Error>().is_none() {
+                logging::log_component_error("KITTY_TABS", &format!("{}", e));
+            }
+            return None;
         }
-    }
+    };
 
-    None
+    let window = windows
+        .into_iter()
+        .find(|w| w.is_active || w.is_focused || w.last_focused)?;
+
+    Some(
+        window
+            .tabs
+            .into_iter()
+            .enumerate()
+            .map(|(index, tab)| TabInfo {
+                id: tab.id,
+                command: tab
+                    .active_window()
+                    .and_then(|w| w.foreground_process())
+                    .and_then(|p| p.command()),
+                bell: tab.needs_attention,
+                activity: tab.has_activity_since_last_focus,
+                title: if tab.title.is_empty() {
+                    format!("Tab {}", index + 1)
+                } else {
+                    tab.title
+                },
+                is_active: tab.is_active,
+            })
+            .collect(),
+    )
 }
 
 #[cfg(test)]
@@ -460,16 +291,15 @@ mod tests {
     #[test]
     fn test_kitty_tabs_new() {
         let kitty_tabs = KittyTabs::new();
-        assert!(kitty_tabs.kitty_pid.is_none() || kitty_tabs.kitty_pid.is_some());
-        assert!(kitty_tabs.socket_path.is_none());
+        assert!(kitty_tabs.kitty.is_some());
+        assert!(kitty_tabs.clicks.is_some());
     }
 
     #[test]
     fn test_kitty_tabs_with_config() {
-        let socket_path = Some("/tmp/custom-kitty".to_string());
-        let kitty_tabs = KittyTabs::with_config(socket_path.clone());
-        assert!(kitty_tabs.kitty_pid.is_none() || kitty_tabs.kitty_pid.is_some());
-        assert_eq!(kitty_tabs.socket_path, socket_path);
+        let kitty_tabs = KittyTabs::with_config(Some("/tmp/custom-kitty".to_string()));
+        assert!(kitty_tabs.kitty.is_some());
+        assert!(kitty_tabs.clicks.is_some());
     }
 
     #[test]
@@ -481,12 +311,36 @@ mod tests {
         kitty_tabs_with_config.update();
     }
 
+    #[test]
+    fn test_discovery_retry() {
+        let mut connection = Connection::default();
+        let start = Instant::now();
+        // No process has this pid, so there's no socket to find
+        let pid = Some(u32::MAX);
+
+        connection.connect(pid, start);
+        assert!(connection.client.is_none());
+        let retry_at = start + DISCOVERY_RETRY_INTERVAL;
+        assert_eq!(connection.discovery_retry_at, Some(retry_at));
+
+        // Not rescanned until the retry is due
+        connection.connect(pid, start + Duration::from_secs(1));
+        assert_eq!(connection.discovery_retry_at, Some(retry_at));
+        connection.connect(pid, retry_at);
+        assert_eq!(
+            connection.discovery_retry_at,
+            Some(retry_at + DISCOVERY_RETRY_INTERVAL)
+        );
+
+        connection.connect(None, retry_at);
+        assert_eq!(connection.discovery_retry_at, None);
+    }
+
     #[test]
     fn test_kitty_tabs_render_empty() {
         let kitty_tabs = KittyTabs {
             tabs: vec![],
-            kitty_pid: None,
-            socket_path: None,
+            ..Default::default()
         };
         let spans = kitty_tabs.render_as_spans(true);
         assert_eq!(spans.len(), 0);
@@ -499,14 +353,15 @@ mod tests {
                 TabInfo {
                     title: "Tab 1".to_string(),
                     is_active: false,
+                    ..Default::default()
                 },
                 TabInfo {
                     title: "Active Tab".to_string(),
                     is_active: true,
+                    ..Default::default()
                 },
             ],
-            kitty_pid: Some(12345),
-            socket_path: None,
+            ..Default::default()
         };
         let spans = kitty_tabs.render_as_spans(true);
         assert_eq!(spans.len(), 2);
@@ -548,9 +403,9 @@ mod tests {
             tabs: vec![TabInfo {
                 title: "opencode help".to_string(),
                 is_active: true,
+                ..Default::default()
             }],
-            kitty_pid: Some(12345),
-            socket_path: None,
+            ..Default::default()
         };
         let spans = kitty_tabs.render_as_spans(true);
         assert_eq!(spans.len(), 1);
@@ -559,4 +414,43 @@ mod tests {
         assert!(content.contains("󰚩")); // OpenCode icon
         assert!(content.contains("opencode"));
     }
+
+    #[test]
+    fn test_program_icon_and_indicators() {
+        let kitty_tabs = KittyTabs {
+            tabs: vec![
+                TabInfo {
+                    id: 1,
+                    title: "~/src/catfood".to_string(),
+                    is_active: true,
+                    command: Some("nvim src/main.rs".to_string()),
+                    bell: true,
+                    ..Default::default()
+                },
+                TabInfo {
+                    id: 2,
+                    title: "build".to_string(),
+                    command: Some("cargo build".to_string()),
+                    activity: true,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let spans = kitty_tabs.render_as_spans(true);
+
+        // The icon comes from the foreground program rather than the title
+        assert_eq!(spans[0].content, " \u{e62b} ~/src/catfood 󰂞 ");
+        assert_eq!(spans[0].style.bg, Some(Color::Rgb(0, 107, 84)));
+        assert_eq!(spans[1].content, " 󱘗 󰑮 • ");
+    }
+
+    #[test]
+    fn test_truncate_title_multibyte() {
+        // Truncation counts characters, so it never splits one
+        assert_eq!(
+            truncate_title("日本語のとても長いタブのタイトルです", true),
+            "日本語のとても長いタブのタ..."
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use crate::component_manager::ComponentManager;
+use crate::components::click_regions::{ClickRegion, click_regions};
 use ratatui::{
     Frame,
+    layout::Alignment,
     prelude::Stylize,
     style::Color,
     text::{Line, Span},
@@ -24,18 +26,20 @@ impl LeftBar {
         frame: &mut Frame,
         area: ratatui::layout::Rect,
         component_manager: &ComponentManager,
-    ) {
+    ) -> Vec<ClickRegion> {
         let components = component_manager.get_bar_components("left");
         let colorize = component_manager.get_colorize();
 
         if components.is_empty() {
-            return;
+            return Vec::new();
         }
 
-        let spans: Vec<Span> = components
+        let rendered: Vec<(&str, Vec<Span>)> = components
             .iter()
-            .flat_map(|component| component.render_as_spans_with_colorize(colorize))
+            .map(|(name, component)| (*name, component.render_as_spans_with_colorize(colorize)))
             .collect();
+        let regions = click_regions(area, Alignment::Left, &rendered);
+        let spans: Vec<Span> = rendered.into_iter().flat_map(|(_, spans)| spans).collect();
 
         let left_line = Line::from(spans);
 
@@ -43,5 +47,6 @@ impl LeftBar {
             Paragraph::new(left_line).left_aligned().fg(Color::White),
             area,
         );
+        regions
     }
 }
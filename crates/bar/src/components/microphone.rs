@@ -0,0 +1,153 @@
+use ratatui::{prelude::Stylize, style::Color, text::Span};
+
+use crate::provider::Subscription;
+use crate::pulse::{self, AudioState, Device};
+
+const DEFAULT_FORMAT: &str = "{icon} {volume}%";
+const MICROPHONE_ICON: &str = "󰍬";
+const MUTED_ICON: &str = "󰍭";
+/// Shown while an application is recording
+const IN_USE_INDICATOR: &str = " ●";
+
+#[derive(Debug)]
+pub struct Microphone {
+    pub level: String,
+    pub is_muted: bool,
+    pub in_use: bool,
+    /// Default source, or `None` without a sound server or microphone
+    pub source: Option<Device>,
+    format: String,
+    cached_span_content: String,
+    /// `None` in tests, which then don't connect to the host's sound server
+    audio: Option<Subscription<AudioState>>,
+}
+
+impl Default for Microphone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Microphone {
+    pub fn new() -> Self {
+        Self::with_config(None)
+    }
+
+    /// `format` may use `{icon}`, `{volume}` and `{name}`
+    pub fn with_config(format: Option<String>) -> Self {
+        let mut microphone = Self {
+            level: String::new(),
+            is_muted: false,
+            in_use: false,
+            source: None,
+            format: format.unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+            cached_span_content: String::new(),
+            audio: Some(pulse::subscribe()),
+        };
+        microphone.update();
+        microphone
+    }
+
+    pub fn update(&mut self) {
+        let state = self.snapshot();
+        self.source = state.source;
+        self.in_use = state.capturing;
+        let (level, is_muted) = self
+            .source
+            .as_ref()
+            .map_or((0, false), |source| (source.volume, source.muted));
+        self.level = level.to_string();
+        self.is_muted = is_muted;
+        self.cached_span_content = self.format_content();
+    }
+
+    fn snapshot(&self) -> AudioState {
+        self.audio
+            .as_ref()
+            .map(Subscription::snapshot)
+            .unwrap_or_default()
+    }
+
+    fn format_content(&self) -> String {
+        let icon = if self.is_muted {
+            MUTED_ICON
+        } else {
+            MICROPHONE_ICON
+        };
+        let name = self
+            .source
+            .as_ref()
+            .map(|source| source.description.as_str())
+            .unwrap_or_default();
+
+        self.format
+            .replace("{icon}", icon)
+            .replace("{volume}", &self.level)
+            .replace("{name}", name)
+    }
+
+    pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
+        let mut spans = if self.is_muted || !colorize {
+            vec![Span::raw(&self.cached_span_content)]
+        } else {
+            vec![Span::raw(&self.cached_span_content).fg(Color::White)]
+        };
+        if self.in_use {
+            spans.push(if colorize {
+                Span::raw(IN_USE_INDICATOR).fg(Color::Red)
+            } else {
+                Span::raw(IN_USE_INDICATOR)
+            });
+        }
+        spans
+    }
+
+    /// Toggle mute on the default source
+    pub fn on_click(&mut self, _span: usize) {
+        if self.source.is_none() {
+            return;
+        }
+        pulse::set_microphone_muted(!self.is_muted);
+        // Show the new state right away; the subscription confirms it shortly
+        self.is_muted = !self.is_muted;
+        self.cached_span_content = self.format_content();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component_manager::Component;
+
+    #[test]
+    fn test_render_in_use() {
+        let mut microphone = Microphone {
+            level: "80".to_string(),
+            is_muted: true,
+            in_use: true,
+            source: None,
+            format: DEFAULT_FORMAT.to_string(),
+            cached_span_content: String::new(),
+            audio: None,
+        };
+        microphone.cached_span_content = microphone.format_content();
+        let mut component = Component::Microphone(microphone);
+
+        // Muting dims the level, but recording stays red
+        let spans = component.render_as_spans_with_muting_and_colorize(true);
+        assert_eq!(spans[0].content, "󰍭 80%");
+        assert_eq!(spans[0].style.fg, Some(Color::DarkGray));
+        assert_eq!(spans[1].content, IN_USE_INDICATOR);
+        assert_eq!(spans[1].style.fg, Some(Color::Red));
+
+        if let Component::Microphone(microphone) = &mut component {
+            microphone.in_use = false;
+        }
+        assert_eq!(
+            component
+                .render_as_spans_with_muting_and_colorize(true)
+                .len(),
+            1
+        );
+    }
+}
@@ -1,10 +1,14 @@
 pub mod battery;
 pub mod brightness;
+pub mod click_regions;
 pub mod cpu;
 pub mod error_icon;
+pub mod failed;
 pub mod kitty_tabs;
 pub mod left_bar;
+pub mod microphone;
 pub mod middle_bar;
+pub mod network;
 pub mod ram;
 pub mod right_bar;
 pub mod separator;
@@ -12,6 +16,7 @@ pub mod space;
 pub mod sparkline;
 pub mod temperature;
 pub mod time;
+pub mod tmux;
 pub mod volume;
 pub mod weather;
 pub mod wifi;
@@ -20,11 +25,15 @@ pub mod workspaces;
 
 pub use battery::Battery;
 pub use brightness::Brightness;
+pub use click_regions::ClickRegion;
 pub use cpu::Cpu;
 pub use error_icon::ErrorIcon;
+pub use failed::Failed;
 pub use kitty_tabs::KittyTabs;
 pub use left_bar::LeftBar;
+pub use microphone::Microphone;
 pub use middle_bar::MiddleBar;
+pub use network::Network;
 pub use ram::Ram;
 pub use right_bar::RightBar;
 pub use separator::Separator;
@@ -32,6 +41,7 @@ pub use space::Space;
 pub use sparkline::Sparkline;
 pub use temperature::Temperature;
 pub use time::Time;
+pub use tmux::Tmux;
 pub use volume::Volume;
 pub use weather::Weather;
 pub use wifi::Wifi;
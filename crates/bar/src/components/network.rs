@@ -0,0 +1,504 @@
+use crate::provider::{NetDevSnapshot, Subscription, net_dev};
+use crate::wireless;
+use ratatui::{prelude::Stylize, style::Color, text::Span};
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FORMAT: &str = "{icon} {ipv4}";
+const ETHERNET_ICON: &str = "󰈀";
+const ETHERNET_DOWN_ICON: &str = "󰈂";
+const WIFI_ICON: &str = "󰤨";
+const WIFI_DOWN_ICON: &str = "󰤮";
+const VIRTUAL_ICON: &str = "󰖂";
+/// `type` of loopback devices in sysfs (`ARPHRD_LOOPBACK`)
+const LOOPBACK_TYPE: &str = "772";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterfaceKind {
+    Ethernet,
+    Wireless,
+    /// Software devices such as VPN tunnels, bridges and container veths
+    Virtual,
+    Loopback,
+}
+
+impl InterfaceKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            InterfaceKind::Ethernet => "ethernet",
+            InterfaceKind::Wireless => "wifi",
+            InterfaceKind::Virtual => "virtual",
+            InterfaceKind::Loopback => "loopback",
+        }
+    }
+}
+
+/// A network interface under `/sys/class/net`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub name: String,
+    pub kind: InterfaceKind,
+    pub up: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Addresses {
+    pub ipv4: Option<Ipv4Addr>,
+    /// A global address if there is one, otherwise link-local
+    pub ipv6: Option<Ipv6Addr>,
+}
+
+/// Throughput in bytes per second
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rates {
+    pub down: f64,
+    pub up: f64,
+}
+
+/// Every interface under `sys_root/class/net`, sorted by name
+pub fn list_interfaces(sys_root: &Path) -> Vec<Interface> {
+    let Ok(entries) = std::fs::read_dir(sys_root.join("class/net")) else {
+        return Vec::new();
+    };
+    let mut interfaces: Vec<_> = entries
+        .flatten()
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let dir = entry.path();
+            let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap_or_default();
+
+            let kind = if read("type").trim() == LOOPBACK_TYPE {
+                InterfaceKind::Loopback
+            } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+                InterfaceKind::Wireless
+            } else if sys_root.join("devices/virtual/net").join(&name).exists() {
+                InterfaceKind::Virtual
+            } else {
+                InterfaceKind::Ethernet
+            };
+            // Tunnels without link state report "unknown" while carrying traffic
+            let up = match read("operstate").trim() {
+                "up" => true,
+                "unknown" => read("carrier").trim() == "1",
+                _ => false,
+            };
+
+            Interface { name, kind, up }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+/// The interface of the lowest-metric default IPv4 route in `proc_root/net/route`
+pub fn default_route_interface(proc_root: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(proc_root.join("net/route")).ok()?;
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let metric: u32 = fields.get(6)?.parse().ok()?;
+            (fields.get(1) == Some(&"00000000")).then(|| (metric, fields[0]))
+        })
+        .min()
+        .map(|(_, name)| name.to_string())
+}
+
+/// `wanted` by name, else the default route's interface, else the first physical one that's up
+pub fn select_interface<'a>(
+    interfaces: &'a [Interface],
+    wanted: Option<&str>,
+    default_route: Option<&str>,
+) -> Option<&'a Interface> {
+    if let Some(wanted) = wanted {
+        return interfaces.iter().find(|i| i.name == wanted);
+    }
+    if let Some(interface) =
+        default_route.and_then(|name| interfaces.iter().find(|i| i.name == name))
+    {
+        return Some(interface);
+    }
+
+    let physical =
+        |i: &&Interface| matches!(i.kind, InterfaceKind::Ethernet | InterfaceKind::Wireless);
+    interfaces
+        .iter()
+        .filter(physical)
+        .find(|i| i.up)
+        // Still show a disconnected icon for the first physical interface
+        .or_else(|| interfaces.iter().find(physical))
+}
+
+/// Throughput of `name` between two snapshots
+pub fn rates(previous: &NetDevSnapshot, current: &NetDevSnapshot, name: &str) -> Option<Rates> {
+    let elapsed = current
+        .read_at?
+        .checked_duration_since(previous.read_at?)?
+        .as_secs_f64();
+    if elapsed <= 0.0 {
+        return None;
+    }
+    let (before, after) = (previous.interface(name)?, current.interface(name)?);
+    Some(Rates {
+        down: after.rx_bytes.saturating_sub(before.rx_bytes) as f64 / elapsed,
+        up: after.tx_bytes.saturating_sub(before.tx_bytes) as f64 / elapsed,
+    })
+}
+
+/// `512 B/s`, `1.5 KiB/s`, `23 MiB/s`
+pub fn format_rate(bytes_per_second: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+    let mut value = bytes_per_second.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 10.0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Addresses of every interface, from `getifaddrs` (which asks the kernel over netlink)
+pub fn interface_addresses() -> HashMap<String, Addresses> {
+    let mut addresses: HashMap<String, Addresses> = HashMap::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return addresses;
+    }
+
+    let mut entry = list;
+    while let Some(ifaddr) = unsafe { entry.as_ref() } {
+        entry = ifaddr.ifa_next;
+        let Some(address) = (unsafe { ifaddr.ifa_addr.as_ref() }) else {
+            continue;
+        };
+        let name = unsafe { CStr::from_ptr(ifaddr.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        let slot = addresses.entry(name).or_default();
+
+        match address.sa_family as i32 {
+            libc::AF_INET if slot.ipv4.is_none() => {
+                let address = unsafe { &*(ifaddr.ifa_addr as *const libc::sockaddr_in) };
+                slot.ipv4 = Some(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)));
+            }
+            libc::AF_INET6 => {
+                let address = unsafe { &*(ifaddr.ifa_addr as *const libc::sockaddr_in6) };
+                let ipv6 = Ipv6Addr::from(address.sin6_addr.s6_addr);
+                if slot
+                    .ipv6
+                    .is_none_or(|current| current.is_unicast_link_local())
+                {
+                    slot.ipv6 = Some(ipv6);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    unsafe { libc::freeifaddrs(list) };
+    addresses
+}
+
+#[derive(Debug)]
+pub struct Network {
+    pub interface: Option<Interface>,
+    pub addresses: Addresses,
+    pub ssid: Option<String>,
+    pub rates: Option<Rates>,
+    /// The `interface` option
+    wanted: Option<String>,
+    format: String,
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+    /// Counters shared with other components; read straight from `proc_root` when `None`
+    net_dev: Option<Subscription<NetDevSnapshot>>,
+    seen_version: Option<u64>,
+    previous: Option<NetDevSnapshot>,
+    cached_span_content: String,
+}
+
+impl Network {
+    /// `format` may use `{icon}`, `{interface}`, `{type}`, `{ipv4}`, `{ipv6}`, `{ssid}`, `{down}`
+    /// and `{up}`
+    pub fn with_config(interface: Option<String>, format: Option<String>) -> Self {
+        let mut network =
+            Self::with_roots(Path::new("/proc"), Path::new("/sys"), interface, format);
+        network.net_dev = Some(net_dev::subscribe());
+        network
+    }
+
+    pub fn with_roots(
+        proc_root: &Path,
+        sys_root: &Path,
+        interface: Option<String>,
+        format: Option<String>,
+    ) -> Self {
+        let mut network = Self {
+            interface: None,
+            addresses: Addresses::default(),
+            ssid: None,
+            rates: None,
+            wanted: interface,
+            format: format.unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+            proc_root: proc_root.to_path_buf(),
+            sys_root: sys_root.to_path_buf(),
+            net_dev: None,
+            seen_version: None,
+            previous: None,
+            cached_span_content: String::new(),
+        };
+        network.update();
+        network
+    }
+
+    pub fn update(&mut self) {
+        let snapshot = match &self.net_dev {
+            // Counters are re-read every second, so that's when everything else is too
+            Some(net_dev) => {
+                let version = net_dev.version();
+                if self.seen_version == Some(version) {
+                    return;
+                }
+                self.seen_version = Some(version);
+                net_dev.snapshot()
+            }
+            None => net_dev::read(&self.proc_root).unwrap_or_default(),
+        };
+        self.refresh(snapshot, &interface_addresses());
+    }
+
+    fn refresh(&mut self, snapshot: NetDevSnapshot, addresses: &HashMap<String, Addresses>) {
+        let interfaces = list_interfaces(&self.sys_root);
+        let default_route = default_route_interface(&self.proc_root);
+        let interface = select_interface(
+            &interfaces,
+            self.wanted.as_deref(),
+            default_route.as_deref(),
+        )
+        .cloned();
+
+        self.addresses = interface
+            .as_ref()
+            .and_then(|i| addresses.get(&i.name))
+            .cloned()
+            .unwrap_or_default();
+        self.ssid = interface
+            .as_ref()
+            .filter(|i| i.kind == InterfaceKind::Wireless && i.up)
+            .and_then(|i| wireless::ssid(&i.name));
+        self.rates = interface.as_ref().and_then(|i| {
+            self.previous
+                .as_ref()
+                .and_then(|previous| rates(previous, &snapshot, &i.name))
+        });
+        self.previous = Some(snapshot);
+        self.interface = interface;
+        self.cached_span_content = self.format_content();
+    }
+
+    fn icon(&self) -> &'static str {
+        match &self.interface {
+            Some(Interface {
+                kind: InterfaceKind::Wireless,
+                up,
+                ..
+            }) => {
+                if *up {
+                    WIFI_ICON
+                } else {
+                    WIFI_DOWN_ICON
+                }
+            }
+            Some(Interface {
+                kind: InterfaceKind::Virtual | InterfaceKind::Loopback,
+                ..
+            }) => VIRTUAL_ICON,
+            Some(Interface { up: true, .. }) => ETHERNET_ICON,
+            _ => ETHERNET_DOWN_ICON,
+        }
+    }
+
+    fn format_content(&self) -> String {
+        let to_string = |value: Option<String>| value.unwrap_or_default();
+        let rates = self.rates.unwrap_or_default();
+        self.format
+            .replace("{icon}", self.icon())
+            .replace(
+                "{interface}",
+                self.interface.as_ref().map_or("", |i| i.name.as_str()),
+            )
+            .replace(
+                "{type}",
+                self.interface.as_ref().map_or("", |i| i.kind.label()),
+            )
+            .replace(
+                "{ipv4}",
+                &to_string(self.addresses.ipv4.map(|a| a.to_string())),
+            )
+            .replace(
+                "{ipv6}",
+                &to_string(self.addresses.ipv6.map(|a| a.to_string())),
+            )
+            .replace("{ssid}", self.ssid.as_deref().unwrap_or_default())
+            .replace("{down}", &format_rate(rates.down))
+            .replace("{up}", &format_rate(rates.up))
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
+        let span = Span::raw(&self.cached_span_content);
+        if colorize {
+            let color = if self.interface.as_ref().is_some_and(|i| i.up) {
+                Color::Blue // Connected: Blue
+            } else {
+                Color::Red // Disconnected: Red
+            };
+            vec![span.fg(color)]
+        } else {
+            vec![span]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn add_interface(sys_root: &Path, name: &str, operstate: &str, kind: InterfaceKind) {
+        let dir = sys_root.join("class/net").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("operstate"), format!("{}\n", operstate)).unwrap();
+        let arp_type = if kind == InterfaceKind::Loopback {
+            LOOPBACK_TYPE
+        } else {
+            "1"
+        };
+        std::fs::write(dir.join("type"), format!("{}\n", arp_type)).unwrap();
+        match kind {
+            InterfaceKind::Wireless => std::fs::create_dir(dir.join("wireless")).unwrap(),
+            InterfaceKind::Virtual | InterfaceKind::Loopback => {
+                std::fs::create_dir_all(sys_root.join("devices/virtual/net").join(name)).unwrap()
+            }
+            InterfaceKind::Ethernet => {}
+        }
+    }
+
+    fn write_net_dev(proc_root: &Path, wlan_rx: u64, wlan_tx: u64) {
+        std::fs::write(
+            proc_root.join("net/dev"),
+            format!(
+                "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:    1000      10    0    0    0     0          0         0     1000      10    0    0    0     0       0          0
+  eth0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+ wlan0: {wlan_rx}     100    0    0    0     0          0         0 {wlan_tx}      50    0    0    0     0       0          0
+"
+            ),
+        )
+        .unwrap();
+    }
+
+    fn fixture() -> (tempfile::TempDir, tempfile::TempDir) {
+        let (proc_root, sys_root) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        add_interface(sys_root.path(), "lo", "unknown", InterfaceKind::Loopback);
+        add_interface(sys_root.path(), "eth0", "down", InterfaceKind::Ethernet);
+        add_interface(sys_root.path(), "wlan0", "up", InterfaceKind::Wireless);
+        add_interface(sys_root.path(), "wg0", "unknown", InterfaceKind::Virtual);
+        std::fs::write(sys_root.path().join("class/net/wg0/carrier"), "1\n").unwrap();
+
+        std::fs::create_dir(proc_root.path().join("net")).unwrap();
+        std::fs::write(
+            proc_root.path().join("net/route"),
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+",
+        )
+        .unwrap();
+        write_net_dev(proc_root.path(), 1_000_000, 200_000);
+        (proc_root, sys_root)
+    }
+
+    #[test]
+    fn test_select_interface() {
+        let (proc_root, sys_root) = fixture();
+        let interfaces = list_interfaces(sys_root.path());
+        let kinds: Vec<_> = interfaces
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind, i.up))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("eth0", InterfaceKind::Ethernet, false),
+                ("lo", InterfaceKind::Loopback, false),
+                ("wg0", InterfaceKind::Virtual, true),
+                ("wlan0", InterfaceKind::Wireless, true),
+            ]
+        );
+
+        let default_route = default_route_interface(proc_root.path());
+        assert_eq!(default_route.as_deref(), Some("wlan0"));
+        let select = |wanted| select_interface(&interfaces, wanted, None).map(|i| i.name.as_str());
+        assert_eq!(select(None), Some("wlan0"));
+        assert_eq!(select(Some("wg0")), Some("wg0"));
+        assert_eq!(select(Some("missing")), None);
+    }
+
+    #[test]
+    fn test_format_and_rates() {
+        let (proc_root, sys_root) = fixture();
+        let mut network = Network::with_roots(
+            proc_root.path(),
+            sys_root.path(),
+            None,
+            Some("{icon} {interface} {ipv4} {ipv6} ↓{down} ↑{up}".to_string()),
+        );
+
+        let first = network.previous.clone().unwrap();
+        write_net_dev(
+            proc_root.path(),
+            1_000_000 + 3 * 1024 * 1024,
+            200_000 + 1536,
+        );
+        let mut second = net_dev::read(proc_root.path()).unwrap();
+        second.read_at = Some(first.read_at.unwrap() + Duration::from_secs(2));
+        let addresses = HashMap::from([(
+            "wlan0".to_string(),
+            Addresses {
+                ipv4: Some(Ipv4Addr::new(192, 168, 1, 23)),
+                ipv6: Some("2001:db8::23".parse().unwrap()),
+            },
+        )]);
+        network.refresh(second, &addresses);
+
+        assert_eq!(
+            network.rates,
+            Some(Rates {
+                down: 1.5 * 1024.0 * 1024.0,
+                up: 768.0,
+            })
+        );
+        assert_eq!(
+            network.cached_span_content,
+            "󰤨 wlan0 192.168.1.23 2001:db8::23 ↓1.5 MiB/s ↑768 B/s"
+        );
+    }
+
+    #[test]
+    fn test_format_rate() {
+        assert_eq!(format_rate(0.0), "0 B/s");
+        assert_eq!(format_rate(1023.0), "1023 B/s");
+        assert_eq!(format_rate(1536.0), "1.5 KiB/s");
+        assert_eq!(format_rate(23.0 * 1024.0 * 1024.0), "23 MiB/s");
+    }
+}
@@ -1,13 +1,13 @@
 use super::sparkline::Sparkline;
+use crate::provider::{Subscription, SystemSnapshot, system};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
 use std::time::{Duration, Instant};
-use sysinfo::{MemoryRefreshKind, RefreshKind};
 
 #[derive(Debug)]
 pub struct Ram {
     pub usage: String,
     cached_span_content: String,
-    system: sysinfo::System,
+    system: Subscription<SystemSnapshot>,
     last_update: Instant,
     update_interval: Duration,
     sparkline: Sparkline,
@@ -20,10 +20,6 @@ impl Ram {
         sparkline_update_freq: u64,
         sparkline_logarithmic: bool,
     ) -> Self {
-        let system = sysinfo::System::new_with_specifics(
-            RefreshKind::nothing().with_memory(MemoryRefreshKind::everything()),
-        );
-
         let usage = "0".to_string();
         let sparkline = Sparkline::new(sparkline, sparkline_length, sparkline_logarithmic);
         let cached_span_content = if sparkline.enabled {
@@ -35,7 +31,7 @@ impl Ram {
         Self {
             usage,
             cached_span_content,
-            system,
+            system: system::subscribe(),
             last_update: Instant::now(),
             update_interval: Duration::from_secs(sparkline_update_freq),
             sparkline,
@@ -45,11 +41,7 @@ impl Ram {
     pub fn update(&mut self) {
         let now = Instant::now();
         if now.duration_since(self.last_update) >= self.update_interval {
-            self.system.refresh_memory();
-
-            let mem_percent: u32 = (self.system.used_memory() as f64
-                / self.system.total_memory() as f64
-                * 100.0) as u32;
+            let mem_percent = self.system.snapshot().memory_percent();
             self.usage = mem_percent.to_string();
 
             if self.sparkline.enabled {
@@ -66,6 +58,10 @@ impl Ram {
         }
     }
 
+    pub fn next_update(&self) -> Instant {
+        self.last_update + self.update_interval
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
         if colorize {
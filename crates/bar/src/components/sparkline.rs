@@ -23,43 +23,180 @@ impl Sparkline {
         }
     }
 
+    /// Each value scaled to `0.0..=1.0` of the largest one
+    pub fn normalized(&self) -> Vec<f64> {
+        let max_value = self.peak();
+        self.data
+            .iter()
+            .map(|&value| {
+                if value == 0 {
+                    0.0
+                } else if self.logarithmic {
+                    // Log scale: log10(value) / log10(max_value)
+                    (value as f64).log10() / (max_value as f64).log10()
+                } else {
+                    // Linear scale (current behavior)
+                    value as f64 / max_value as f64
+                }
+            })
+            .collect()
+    }
+
+    pub fn peak(&self) -> u64 {
+        self.data.iter().copied().max().unwrap_or(0)
+    }
+
     pub fn render(&self) -> String {
         if !self.enabled {
             return String::new();
         }
 
-        let max_value = self.data.iter().max().unwrap_or(&1);
-        if *max_value == 0 {
-            return " ".repeat(self.length);
+        let bars = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        self.normalized()
+            .into_iter()
+            .map(|normalized| {
+                let index = (normalized * (bars.len() - 1) as f64) as usize;
+                bars[index.min(bars.len() - 1)]
+            })
+            .collect()
+    }
+
+    pub fn render_with_spaces(&self) -> String {
+        if self.enabled {
+            self.render()
+        } else {
+            " ".repeat(self.length)
+        }
+    }
+}
+
+/// How a [`DualSparkline`] lays out its two series
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SparklineLayout {
+    /// One row of half blocks, the first series in the upper halves and the second in the lower.
+    ///
+    /// A half only has room for on or off, so this is an activity indicator rather than a graph:
+    /// a half is lit once its value reaches half the series' peak on the configured scale. On a
+    /// logarithmic scale that's anything above the square root of the peak.
+    #[default]
+    Stacked,
+    /// Two regular sparklines next to each other
+    SideBySide,
+}
+
+impl SparklineLayout {
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value {
+            Some("side_by_side") => Self::SideBySide,
+            _ => Self::Stacked,
         }
+    }
+}
 
-        let bars = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
-        let mut result = String::new();
+/// Two series graphed together, such as download and upload rates
+#[derive(Debug)]
+pub struct DualSparkline {
+    pub upper: Sparkline,
+    pub lower: Sparkline,
+    pub layout: SparklineLayout,
+}
 
-        for &value in &self.data {
-            if value == 0 {
-                result.push(' ');
-            } else {
-                let normalized = if self.logarithmic {
-                    // Log scale: log10(value) / log10(max_value)
-                    (value as f64).log10() / (*max_value as f64).log10()
-                } else {
-                    // Linear scale (current behavior)
-                    value as f64 / *max_value as f64
-                };
-                let index = (normalized * (bars.len() - 1) as f64) as usize;
-                result.push(bars[index.min(bars.len() - 1)].chars().next().unwrap());
-            }
+impl DualSparkline {
+    pub fn new(enabled: bool, length: usize, logarithmic: bool, layout: SparklineLayout) -> Self {
+        Self {
+            upper: Sparkline::new(enabled, length, logarithmic),
+            lower: Sparkline::new(enabled, length, logarithmic),
+            layout,
         }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.upper.enabled
+    }
 
-        result
+    pub fn update(&mut self, upper: u64, lower: u64) {
+        self.upper.update(upper);
+        self.lower.update(lower);
+    }
+
+    pub fn render(&self) -> String {
+        if !self.enabled() {
+            return String::new();
+        }
+
+        match self.layout {
+            SparklineLayout::SideBySide => {
+                format!("{} {}", self.upper.render(), self.lower.render())
+            }
+            SparklineLayout::Stacked => self
+                .upper
+                .normalized()
+                .into_iter()
+                .zip(self.lower.normalized())
+                .map(|(upper, lower)| match (upper >= 0.5, lower >= 0.5) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                })
+                .collect(),
+        }
     }
 
     pub fn render_with_spaces(&self) -> String {
-        if self.enabled {
+        if self.enabled() {
             self.render()
         } else {
-            " ".repeat(self.length)
+            " ".repeat(self.width())
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self.layout {
+            SparklineLayout::Stacked => self.upper.length,
+            SparklineLayout::SideBySide => self.upper.length * 2 + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut sparkline = Sparkline::new(true, 4, false);
+        for value in [0, 1, 4, 8] {
+            sparkline.update(value);
         }
+        assert_eq!(sparkline.render(), " ▁▄█");
+        assert_eq!(sparkline.peak(), 8);
+    }
+
+    #[test]
+    fn test_dual_render() {
+        let mut sparkline = DualSparkline::new(true, 4, false, SparklineLayout::Stacked);
+        for (upper, lower) in [(0, 0), (10, 1), (2, 8), (10, 8)] {
+            sparkline.update(upper, lower);
+        }
+        assert_eq!(sparkline.render(), " ▀▄█");
+
+        sparkline.layout = SparklineLayout::SideBySide;
+        assert_eq!(sparkline.render(), " █▁█  ▁██");
+        assert_eq!(sparkline.render_with_spaces().chars().count(), 9);
+    }
+
+    #[test]
+    fn test_dual_render_logarithmic() {
+        let mut sparkline = DualSparkline::new(true, 3, true, SparklineLayout::Stacked);
+        for (upper, lower) in [(1024 * 1024, 0), (4096, 0), (600 * 1024, 300)] {
+            sparkline.update(upper, lower);
+        }
+        // 4 KiB/s is above the square root of 1 MiB/s, so it shows next to the peak
+        assert_eq!(sparkline.render(), "▀▀█");
+
+        // Tiny trickles stay dark
+        sparkline.update(16, 8);
+        assert_eq!(sparkline.render(), "▀█ ");
     }
 }
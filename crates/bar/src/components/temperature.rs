@@ -1,12 +1,12 @@
+use crate::provider::{Subscription, SystemSnapshot, system};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
 use std::time::{Duration, Instant};
-use sysinfo::Components;
 
 #[derive(Debug)]
 pub struct Temperature {
     pub value: String,
     cached_span_content: String,
-    components: Components,
+    system: Subscription<SystemSnapshot>,
     last_update: Instant,
     update_interval: Duration,
 }
@@ -19,14 +19,13 @@ impl Default for Temperature {
 
 impl Temperature {
     pub fn new() -> Self {
-        let components = Components::new();
         let value = "0".to_string();
         let cached_span_content = format!(" {}°C", value);
 
         Self {
             value,
             cached_span_content,
-            components,
+            system: system::subscribe(),
             last_update: Instant::now(),
             update_interval: Duration::from_secs(5),
         }
@@ -35,14 +34,7 @@ impl Temperature {
     pub fn update(&mut self) {
         let now = Instant::now();
         if now.duration_since(self.last_update) >= self.update_interval {
-            self.components.refresh(true);
-
-            if let Some(component) = self.components.iter().find(|c| {
-                c.label().to_lowercase().contains("cpu")
-                    || c.label().to_lowercase().contains("core")
-                    || c.label().to_lowercase().contains("package")
-            }) && let Some(temp) = component.temperature()
-            {
+            if let Some(temp) = self.system.snapshot().cpu_temperature {
                 self.value = format!("{:.0}", temp);
                 self.cached_span_content = format!(" {}°C", self.value);
             }
@@ -51,6 +43,10 @@ impl Temperature {
         }
     }
 
+    pub fn next_update(&self) -> Instant {
+        self.last_update + self.update_interval
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
         if colorize {
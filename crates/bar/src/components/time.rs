@@ -1,6 +1,9 @@
 use crate::time_utils;
-use chrono::Local;
+use chrono::{Local, Timelike};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
+use std::time::{Duration, Instant};
+
+const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
 #[derive(Debug, Clone)]
 pub struct Time {
@@ -8,14 +11,14 @@ pub struct Time {
     pub cached_span_content: String,
     day_start: u8,
     night_start: u8,
+    format: String,
+    /// Whether `format` shows seconds, so the clock must tick every second
+    shows_seconds: bool,
 }
 
 impl Default for Time {
     fn default() -> Self {
-        Self::with_config(
-            time_utils::default_day_start(),
-            time_utils::default_night_start(),
-        )
+        Self::new()
     }
 }
 
@@ -24,24 +27,41 @@ impl Time {
         Self::with_config(
             time_utils::default_day_start(),
             time_utils::default_night_start(),
+            None,
         )
     }
 
-    pub fn with_config(day_start: u8, night_start: u8) -> Self {
-        let time_string = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-        Self {
-            time_string: time_string.clone(),
-            cached_span_content: time_string,
+    /// `format` takes chrono's strftime-like specifiers
+    pub fn with_config(day_start: u8, night_start: u8, format: Option<String>) -> Self {
+        let format = format.unwrap_or_else(|| DEFAULT_FORMAT.to_string());
+        let mut time = Self {
+            time_string: String::new(),
+            cached_span_content: String::new(),
             day_start,
             night_start,
-        }
+            shows_seconds: shows_seconds(&format),
+            format,
+        };
+        time.update();
+        time
     }
 
     pub fn update(&mut self) {
-        self.time_string = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.time_string = Local::now().format(&self.format).to_string();
         self.cached_span_content = self.time_string.clone();
     }
 
+    /// The start of the next second, or of the next minute if seconds aren't shown
+    pub fn next_update(&self) -> Instant {
+        let now = Local::now();
+        let mut until =
+            Duration::from_nanos(1_000_000_000 - now.nanosecond() as u64 % 1_000_000_000);
+        if !self.shows_seconds {
+            until += Duration::from_secs(59 - now.second() as u64);
+        }
+        Instant::now() + until
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
         if colorize {
@@ -57,3 +77,35 @@ impl Time {
         }
     }
 }
+
+/// Format two instants a second apart; if they look the same, seconds aren't shown
+fn shows_seconds(format: &str) -> bool {
+    let now = Local::now();
+    let (Some(a), Some(b)) = (now.with_second(0), now.with_second(1)) else {
+        return true;
+    };
+    a.format(format).to_string() != b.format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_update() {
+        assert!(shows_seconds(DEFAULT_FORMAT));
+        assert!(shows_seconds("%T"));
+        assert!(!shows_seconds("%H:%M"));
+        assert!(!shows_seconds("%a %d %b %R"));
+
+        let seconds = Time::with_config(6, 18, None);
+        assert!(seconds.next_update() <= Instant::now() + Duration::from_secs(1));
+
+        let minutes = Time::with_config(6, 18, Some("%H:%M".to_string()));
+        let until = minutes.next_update() - Instant::now();
+        assert!(until <= Duration::from_secs(60));
+        // Lands on a minute boundary
+        let next = Local::now() + until;
+        assert!(next.second() == 0 || next.second() == 59);
+    }
+}
@@ -0,0 +1,164 @@
+use ratatui::{prelude::Stylize, style::Color, text::Span};
+use std::path::Path;
+
+use crate::tmux::{self, TmuxWindow};
+use crate::window_manager::WindowManager;
+use crate::{logging, window_manager};
+use std::sync::Arc;
+
+const ACTIVITY_FLAG: &str = "#";
+const BELL_FLAG: &str = "!";
+
+/// Which session's windows to show
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SessionFilter {
+    /// The session of the most recently used tmux client
+    #[default]
+    Attached,
+    /// The session running in the focused terminal, if any
+    Focused,
+}
+
+impl SessionFilter {
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value {
+            Some("focused") => SessionFilter::Focused,
+            _ => SessionFilter::Attached,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Tmux {
+    pub windows: Vec<TmuxWindow>,
+    filter: SessionFilter,
+    /// Only needed to find the focused window's session
+    window_manager: Option<Arc<dyn WindowManager>>,
+}
+
+impl Tmux {
+    pub fn new() -> Self {
+        Self::with_config(SessionFilter::default())
+    }
+
+    pub fn with_config(filter: SessionFilter) -> Self {
+        let window_manager = match filter {
+            SessionFilter::Focused => window_manager::subscribe(),
+            SessionFilter::Attached => None,
+        };
+        let mut tmux = Self {
+            windows: Vec::new(),
+            filter,
+            window_manager,
+        };
+        tmux.update();
+        tmux
+    }
+
+    pub fn update(&mut self) {
+        let state = tmux::shared().state();
+        let session = match self.filter {
+            SessionFilter::Attached => state.attached_session(),
+            SessionFilter::Focused => get_focused_pid(self.window_manager.as_deref())
+                .and_then(|pid| state.session_for_pid(pid, Path::new("/proc"))),
+        };
+
+        self.windows = session
+            .map(|session| state.windows_in(session).cloned().collect())
+            .unwrap_or_default();
+        self.windows.sort_by_key(|w| w.index);
+    }
+
+    pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
+        self.windows
+            .iter()
+            .map(|window| {
+                let content = format!(" {}:{}{} ", window.index, window.name, flags(window));
+                if window.active {
+                    if colorize {
+                        Span::raw(content).bg(Color::Green).fg(Color::Black)
+                    } else {
+                        Span::raw(content).bg(Color::White).fg(Color::Black)
+                    }
+                } else if colorize && window.bell {
+                    Span::raw(content).fg(Color::Red)
+                } else if colorize && window.activity {
+                    Span::raw(content).fg(Color::Yellow)
+                } else {
+                    Span::raw(content).fg(Color::White)
+                }
+            })
+            .collect()
+    }
+
+    /// Select the clicked window; spans map one-to-one to windows
+    pub fn on_click(&mut self, span: usize) {
+        let Some(window) = self.windows.get(span) else {
+            return;
+        };
+        if let Err(e) = tmux::shared().select_window(&window.id) {
+            logging::log_component_error("TMUX", &format!("{}", e));
+        }
+    }
+}
+
+/// tmux's own status line flags for windows that need attention
+fn flags(window: &TmuxWindow) -> &'static str {
+    if window.bell {
+        BELL_FLAG
+    } else if window.activity && !window.active {
+        ACTIVITY_FLAG
+    } else {
+        ""
+    }
+}
+
+fn get_focused_pid(window_manager: Option<&dyn WindowManager>) -> Option<u32> {
+    window_manager?.state().focused_window()?.pid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(index: u32, name: &str, active: bool, activity: bool, bell: bool) -> TmuxWindow {
+        TmuxWindow {
+            id: format!("@{}", index),
+            session: "work".to_string(),
+            index,
+            name: name.to_string(),
+            active,
+            activity,
+            bell,
+        }
+    }
+
+    #[test]
+    fn test_render_flags() {
+        let tmux = Tmux {
+            windows: vec![
+                window(0, "vim", true, false, false),
+                window(1, "build", false, true, false),
+                window(2, "logs", false, true, true),
+            ],
+            filter: SessionFilter::Attached,
+            window_manager: None,
+        };
+
+        let spans = tmux.render_as_spans(true);
+        let contents: Vec<_> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(contents, vec![" 0:vim ", " 1:build# ", " 2:logs! "]);
+        assert_eq!(spans[0].style.bg, Some(Color::Green));
+        assert_eq!(spans[1].style.fg, Some(Color::Yellow));
+        assert_eq!(spans[2].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_session_filter_from_config() {
+        assert_eq!(
+            SessionFilter::from_config(Some("focused")),
+            SessionFilter::Focused
+        );
+        assert_eq!(SessionFilter::from_config(None), SessionFilter::Attached);
+    }
+}
@@ -1,13 +1,21 @@
 use ratatui::{prelude::Stylize, style::Color, text::Span};
-use std::process::Command;
 
-use crate::logging;
+use crate::provider::Subscription;
+use crate::pulse::{self, AudioState, Device, DeviceKind};
+
+const DEFAULT_FORMAT: &str = "{icon} {volume}%";
+const MUTED_ICON: &str = "󰝟";
 
 #[derive(Debug)]
 pub struct Volume {
     pub level: String,
     pub is_muted: bool,
+    /// Default sink, or `None` without a sound server
+    pub sink: Option<Device>,
+    format: String,
     cached_span_content: String,
+    /// `None` in tests, which then don't connect to the host's sound server
+    audio: Option<Subscription<AudioState>>,
 }
 
 impl Default for Volume {
@@ -18,25 +26,59 @@ impl Default for Volume {
 
 impl Volume {
     pub fn new() -> Self {
-        let (level, is_muted) = get_system_volume().unwrap_or((0, false));
-        let level_str = level.to_string();
-        let icon = if is_muted { "󰝟" } else { "󰕾" };
-        let cached_span_content = format!("{} {}%", icon, level_str);
+        Self::with_config(None)
+    }
 
-        Self {
-            level: level_str,
-            is_muted,
-            cached_span_content,
-        }
+    /// `format` may use `{icon}`, `{volume}`, `{name}` and `{type}`
+    pub fn with_config(format: Option<String>) -> Self {
+        let mut volume = Self {
+            level: String::new(),
+            is_muted: false,
+            sink: None,
+            format: format.unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+            cached_span_content: String::new(),
+            audio: Some(pulse::subscribe()),
+        };
+        volume.update();
+        volume
     }
 
     pub fn update(&mut self) {
-        let (level, is_muted) = get_system_volume().unwrap_or((0, false));
+        self.sink = self.snapshot().sink;
+        let (level, is_muted) = self
+            .sink
+            .as_ref()
+            .map_or((0, false), |sink| (sink.volume, sink.muted));
         self.level = level.to_string();
         self.is_muted = is_muted;
+        self.cached_span_content = self.format_content();
+    }
+
+    fn snapshot(&self) -> AudioState {
+        self.audio
+            .as_ref()
+            .map(Subscription::snapshot)
+            .unwrap_or_default()
+    }
 
-        let icon = if self.is_muted { "󰝟" } else { "󰕾" };
-        self.cached_span_content = format!("{} {}%", icon, self.level);
+    fn format_content(&self) -> String {
+        let kind = self.sink.as_ref().map(|sink| sink.kind).unwrap_or_default();
+        let icon = if self.is_muted {
+            MUTED_ICON
+        } else {
+            kind_icon(kind)
+        };
+        let name = self
+            .sink
+            .as_ref()
+            .map(|sink| sink.description.as_str())
+            .unwrap_or_default();
+
+        self.format
+            .replace("{icon}", icon)
+            .replace("{volume}", &self.level)
+            .replace("{name}", name)
+            .replace("{type}", kind.label())
     }
 
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
@@ -48,31 +90,39 @@ impl Volume {
     }
 }
 
-fn get_system_volume() -> Option<(i32, bool)> {
-    let output = Command::new("wpctl")
-        .args(["get-volume", "@DEFAULT_AUDIO_SINK@"])
-        .output()
-        .expect("failed to get volume");
+fn kind_icon(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Speakers => "󰕾",
+        DeviceKind::Headphones => "󰋋",
+        DeviceKind::Bluetooth => "󰂰",
+    }
+}
 
-    if output.status.success() {
-        let stdout = str::from_utf8(&output.stdout).unwrap();
-        let is_muted = stdout.contains("[MUTED]");
-        let parts: Vec<&str> = stdout.split_whitespace().collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if let Ok(volume) = parts[1].parse::<f32>() {
-            return Some(((volume * 100.0) as i32, is_muted));
-        }
+    #[test]
+    fn test_format() {
+        let mut volume = Volume {
+            level: "35".to_string(),
+            is_muted: false,
+            sink: Some(Device {
+                index: 0,
+                name: "bluez_output.00_1B_66.1".to_string(),
+                description: "WH-1000XM4".to_string(),
+                volume: 35,
+                muted: false,
+                kind: DeviceKind::Bluetooth,
+            }),
+            format: "{icon} {volume}% {name} ({type})".to_string(),
+            cached_span_content: String::new(),
+            audio: None,
+        };
+        assert_eq!(volume.format_content(), "󰂰 35% WH-1000XM4 (bluetooth)");
 
-        logging::log_component_error(
-            "VOLUME",
-            &format!("Failed to parse volume from output: {}", stdout),
-        );
-    } else {
-        logging::log_component_error(
-            "VOLUME",
-            str::from_utf8(&output.stderr).unwrap_or("unknown error"),
-        );
+        volume.is_muted = true;
+        volume.format = DEFAULT_FORMAT.to_string();
+        assert_eq!(volume.format_content(), "󰝟 35%");
     }
-
-    Some((0, false))
 }
@@ -1,8 +1,11 @@
-use crate::time_utils;
+use crate::{time_utils, wakeup};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
 use serde::Deserialize;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long to wait before retrying a fetch that hasn't succeeded yet
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Default, Clone)]
 pub struct WeatherData {
@@ -18,6 +21,7 @@ pub struct Weather {
     last_update: Arc<Mutex<u64>>,
     day_start: u8,
     night_start: u8,
+    last_attempt: Instant,
     _update_handle: tokio::task::JoinHandle<()>,
 }
 
@@ -99,6 +103,7 @@ impl Weather {
                     if let Ok(mut last_update_guard) = last_update_clone.lock() {
                         *last_update_guard = now;
                     }
+                    wakeup::notify();
                 }
             }
         });
@@ -109,6 +114,8 @@ impl Weather {
             last_update,
             day_start,
             night_start,
+            // The background task fetches right away
+            last_attempt: Instant::now(),
             _update_handle: update_handle,
         }
     }
@@ -123,8 +130,10 @@ impl Weather {
 
         if let Ok(last_update_guard) = self.last_update.lock()
             && *last_update_guard == 0
+            && self.last_attempt.elapsed() >= RETRY_INTERVAL
         {
-            // First run, spawn immediate fetch
+            // No data yet, e.g. offline at startup, so retry
+            self.last_attempt = Instant::now();
             let data_clone = self.data.clone();
             let cached_span_content_clone = self.cached_span_content.clone();
             let last_update_clone = self.last_update.clone();
@@ -158,6 +167,7 @@ impl Weather {
                     if let Ok(mut last_update_guard) = last_update_clone.lock() {
                         *last_update_guard = now;
                     }
+                    wakeup::notify();
                 }
             });
         }
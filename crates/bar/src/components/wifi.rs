@@ -1,170 +1,368 @@
-use super::sparkline::Sparkline;
+use super::network::{self, Interface, InterfaceKind, Rates};
+use super::sparkline::{DualSparkline, SparklineLayout};
+use crate::provider::{NetDevSnapshot, Subscription, net_dev};
+use crate::wireless::{self, Signal};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
-use std::process::Command;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+const DISCONNECTED_ICON: &str = "󰤮";
+/// By signal quality, weakest first
+const SIGNAL_ICONS: [&str; 5] = ["󰤯", "󰤟", "󰤢", "󰤥", "󰤨"];
+const DEFAULT_FORMAT: &str = "{icon} {ssid}";
+const SPARKLINE_FORMAT: &str = "{icon} {sparkline} ↓{down} ↑{up}";
+
+/// The network a wireless interface is connected to; each field is `None` when the driver
+/// doesn't answer wireless extensions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Link {
+    pub ssid: Option<String>,
+    /// `None` when `/proc/net/wireless` has no row for the interface
+    pub signal: Option<Signal>,
+    /// In MHz
+    pub frequency: Option<u32>,
+    /// In bits per second
+    pub bitrate: Option<u32>,
+}
+
 #[derive(Debug)]
 pub struct Wifi {
-    pub status: String,
-    pub network: String,
+    pub interface: Option<String>,
+    /// `None` while disconnected
+    pub link: Option<Link>,
+    /// Throughput of `interface`, per second
+    pub rates: Option<Rates>,
+    format: Option<String>,
     cached_span_content: String,
+    /// Download and upload peaks over the sparkline, when `sparkline_peaks` is set
+    cached_peaks: Option<(String, String)>,
     last_update: Instant,
     update_interval: Duration,
-    sparkline: Sparkline,
-    last_bytes: Option<u64>,
+    /// Download above, upload below
+    sparkline: DualSparkline,
+    sparkline_content: String,
+    show_peaks: bool,
+    previous: Option<NetDevSnapshot>,
+    /// `None` in tests, which then leave the host's interfaces alone
+    net_dev: Option<Subscription<NetDevSnapshot>>,
 }
 
 impl Wifi {
+    /// `format` may use `{icon}`, `{ssid}`, `{signal}`, `{dbm}`, `{band}`, `{frequency}`,
+    /// `{bitrate}`, `{down}`, `{up}` and `{sparkline}`
     pub fn with_config(
         sparkline: bool,
         sparkline_length: usize,
         sparkline_update_freq: u64,
         sparkline_logarithmic: bool,
+        sparkline_layout: SparklineLayout,
+        sparkline_peaks: bool,
+        format: Option<String>,
     ) -> Self {
-        let (status, network) =
-            get_wifi_status().unwrap_or(("disconnected".to_string(), "".to_string()));
-
-        let icon = if status == "connected" {
-            "󰤨"
-        } else {
-            "󰤮"
-        };
-
-        let network_text = if status == "connected" && !network.is_empty() {
-            &network
-        } else {
-            "Off"
-        };
-
-        let sparkline = Sparkline::new(sparkline, sparkline_length, sparkline_logarithmic);
-        let cached_span_content = if sparkline.enabled {
-            format!("{} {}", icon, sparkline.render_with_spaces())
-        } else {
-            format!("{} {}", icon, network_text)
-        };
+        let mut wifi = Self::new(
+            sparkline,
+            sparkline_length,
+            sparkline_update_freq,
+            sparkline_logarithmic,
+            sparkline_layout,
+            sparkline_peaks,
+            format,
+        );
+        wifi.net_dev = Some(net_dev::subscribe());
+        wifi.read_link();
+        wifi.refresh_content();
+        wifi
+    }
 
-        Self {
-            status,
-            network,
-            cached_span_content,
+    /// Disconnected, without reading or subscribing to anything
+    fn new(
+        sparkline: bool,
+        sparkline_length: usize,
+        sparkline_update_freq: u64,
+        sparkline_logarithmic: bool,
+        sparkline_layout: SparklineLayout,
+        sparkline_peaks: bool,
+        format: Option<String>,
+    ) -> Self {
+        let sparkline = DualSparkline::new(
+            sparkline,
+            sparkline_length,
+            sparkline_logarithmic,
+            sparkline_layout,
+        );
+        let mut wifi = Self {
+            interface: None,
+            link: None,
+            rates: None,
+            format,
+            cached_span_content: String::new(),
+            cached_peaks: None,
             last_update: Instant::now(),
             update_interval: Duration::from_secs(sparkline_update_freq),
+            sparkline_content: sparkline.render_with_spaces(),
             sparkline,
-            last_bytes: None,
-        }
+            show_peaks: sparkline_peaks,
+            previous: None,
+            net_dev: None,
+        };
+        wifi.refresh_content();
+        wifi
     }
 
     pub fn update(&mut self) {
         let now = Instant::now();
         if now.duration_since(self.last_update) >= self.update_interval {
-            if let Some((status, network)) = get_wifi_status() {
-                self.status = status;
-                self.network = network;
+            self.read_link();
+            self.read_rates();
 
-                let icon = if self.status == "connected" {
-                    "󰤨"
+            if self.sparkline.enabled() {
+                if self.interface.is_some() {
+                    let rates = self.rates.unwrap_or_default();
+                    self.sparkline.update(rates.down as u64, rates.up as u64);
+                    self.sparkline_content = self.sparkline.render();
                 } else {
-                    "󰤮"
-                };
-
-                if self.sparkline.enabled {
-                    if let Some(current_bytes) = get_network_usage() {
-                        let usage = if let Some(last_bytes) = self.last_bytes {
-                            current_bytes.saturating_sub(last_bytes)
-                        } else {
-                            0
-                        };
-
-                        self.last_bytes = Some(current_bytes);
-
-                        // Update sparkline data
-                        self.sparkline.update(usage);
-
-                        // Render sparkline
-                        self.cached_span_content = format!("{} {}", icon, self.sparkline.render());
-                    } else {
-                        self.cached_span_content =
-                            format!("{} {}", icon, self.sparkline.render_with_spaces());
-                    }
-                } else {
-                    let network_text = if self.status == "connected" && !self.network.is_empty() {
-                        &self.network
-                    } else {
-                        "Off"
-                    };
-                    self.cached_span_content = format!("{} {}", icon, network_text);
+                    self.sparkline_content = self.sparkline.render_with_spaces();
                 }
             }
 
+            self.refresh_content();
             self.last_update = now;
         }
     }
 
+    fn read_link(&mut self) {
+        let interface = wireless_interface(Path::new("/sys"));
+        // The operstate is up once associated, even where wireless extensions aren't offered
+        self.link = interface.as_ref().filter(|i| i.up).map(|i| Link {
+            ssid: wireless::ssid(&i.name),
+            signal: wireless::read_signals(Path::new("/proc"))
+                .into_iter()
+                .find(|signal| signal.interface == i.name),
+            frequency: wireless::frequency(&i.name),
+            bitrate: wireless::bitrate(&i.name),
+        });
+        self.interface = interface.map(|i| i.name);
+    }
+
+    fn read_rates(&mut self) {
+        let Some(net_dev) = &self.net_dev else {
+            return;
+        };
+        let snapshot = net_dev.snapshot();
+        // Counters are re-read every second; keep the last rates until they are
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| previous.read_at == snapshot.read_at)
+        {
+            return;
+        }
+        self.rates = self
+            .interface
+            .as_deref()
+            .and_then(|name| network::rates(self.previous.as_ref()?, &snapshot, name));
+        self.previous = Some(snapshot);
+    }
+
+    fn icon(&self) -> &'static str {
+        match &self.link {
+            Some(link) => link
+                .signal
+                .as_ref()
+                .map_or(SIGNAL_ICONS[4], |signal| signal_icon(signal.quality)),
+            None => DISCONNECTED_ICON,
+        }
+    }
+
+    fn refresh_content(&mut self) {
+        let format = match (&self.format, &self.link) {
+            (Some(format), _) => format.as_str(),
+            (None, _) if self.sparkline.enabled() => SPARKLINE_FORMAT,
+            (None, Some(_)) => DEFAULT_FORMAT,
+            (None, None) => "{icon} Off",
+        };
+
+        let link = self.link.clone().unwrap_or_default();
+        let rates = self.rates.unwrap_or_default();
+        let signal = link.signal.as_ref();
+        let to_string = |value: Option<String>| value.unwrap_or_default();
+        self.cached_span_content = format
+            .replace("{icon}", self.icon())
+            .replace("{ssid}", link.ssid.as_deref().unwrap_or_default())
+            .replace(
+                "{signal}",
+                &to_string(signal.map(|s| s.quality.to_string())),
+            )
+            .replace("{dbm}", &to_string(signal.map(|s| s.level.to_string())))
+            .replace(
+                "{band}",
+                link.frequency.and_then(wireless::band).unwrap_or_default(),
+            )
+            .replace(
+                "{frequency}",
+                &to_string(link.frequency.map(|f| f.to_string())),
+            )
+            .replace("{bitrate}", &to_string(link.bitrate.map(format_bitrate)))
+            .replace("{down}", &network::format_rate(rates.down))
+            .replace("{up}", &network::format_rate(rates.up))
+            .replace("{sparkline}", &self.sparkline_content)
+            .trim_end()
+            .to_string();
+
+        self.cached_peaks = (self.show_peaks && self.sparkline.enabled()).then(|| {
+            (
+                format!(
+                    "↓{}",
+                    network::format_rate(self.sparkline.upper.peak() as f64)
+                ),
+                format!(
+                    "↑{}",
+                    network::format_rate(self.sparkline.lower.peak() as f64)
+                ),
+            )
+        });
+    }
+
+    pub fn next_update(&self) -> Instant {
+        self.last_update + self.update_interval
+    }
+
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
         let span = Span::raw(&self.cached_span_content);
-        if colorize {
-            let color = if self.status == "disconnected" {
-                Color::Red // Disconnected: Red
-            } else {
-                Color::Blue // Connected: Blue
+        let mut spans = if colorize {
+            let color = match &self.link {
+                None => Color::Red, // Disconnected: Red
+                Some(link) => signal_color(link.signal.as_ref().map_or(100, |s| s.quality)),
             };
             vec![span.fg(color)]
         } else {
             vec![span]
+        };
+
+        if let Some((down, up)) = &self.cached_peaks {
+            spans.push(Span::raw(" peak "));
+            if colorize {
+                spans.extend([
+                    Span::raw(down).cyan(),
+                    Span::raw(" "),
+                    Span::raw(up).magenta(),
+                ]);
+            } else {
+                spans.extend([Span::raw(down), Span::raw(" "), Span::raw(up)]);
+            }
         }
+        spans
+    }
+}
+
+/// The first connected wireless interface, or the first one at all
+fn wireless_interface(sys_root: &Path) -> Option<Interface> {
+    let mut interfaces = network::list_interfaces(sys_root)
+        .into_iter()
+        .filter(|i| i.kind == InterfaceKind::Wireless);
+    let first = interfaces.next()?;
+    if first.up {
+        return Some(first);
     }
+    Some(interfaces.find(|i| i.up).unwrap_or(first))
 }
 
-fn get_wifi_status() -> Option<(String, String)> {
-    let output = Command::new("nmcli")
-        .args(["-t", "-f", "TYPE,STATE,CONNECTION", "device"])
-        .output()
-        .ok()?;
+fn signal_icon(quality: u32) -> &'static str {
+    SIGNAL_ICONS[(quality.min(99) / 20) as usize]
+}
 
-    if !output.status.success() {
-        return None;
+fn signal_color(quality: u32) -> Color {
+    match quality {
+        0..35 => Color::LightRed, // Weak: Light red
+        35..60 => Color::Yellow,  // Fair: Yellow
+        _ => Color::Blue,         // Good: Blue
     }
+}
 
-    let stdout = str::from_utf8(&output.stdout).ok()?;
+/// `866 Mb/s`
+fn format_bitrate(bits_per_second: u32) -> String {
+    format!("{:.0} Mb/s", bits_per_second as f64 / 1e6)
+}
 
-    for line in stdout.lines() {
-        if line.starts_with("wifi:") {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() >= 3 {
-                let state = parts[1].to_lowercase();
-                let connection = parts[2].to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                if state == "connected" {
-                    return Some(("connected".to_string(), connection));
-                } else {
-                    return Some(("disconnected".to_string(), "".to_string()));
-                }
-            }
+    #[test]
+    fn test_signal_icon() {
+        assert_eq!(signal_icon(0), "󰤯");
+        assert_eq!(signal_icon(19), "󰤯");
+        assert_eq!(signal_icon(45), "󰤢");
+        assert_eq!(signal_icon(80), "󰤨");
+        assert_eq!(signal_icon(100), "󰤨");
+        assert_eq!(signal_color(20), Color::LightRed);
+        assert_eq!(signal_color(77), Color::Blue);
+    }
+
+    #[test]
+    fn test_wireless_interface() {
+        let sys_root = tempfile::tempdir().unwrap();
+        for (name, operstate) in [("wlan0", "down"), ("wlan1", "up")] {
+            let dir = sys_root.path().join("class/net").join(name);
+            std::fs::create_dir_all(dir.join("wireless")).unwrap();
+            std::fs::write(dir.join("operstate"), operstate).unwrap();
         }
+        std::fs::create_dir_all(sys_root.path().join("class/net/eth0")).unwrap();
+
+        let interface = wireless_interface(sys_root.path()).unwrap();
+        assert_eq!((interface.name.as_str(), interface.up), ("wlan1", true));
     }
 
-    Some(("disconnected".to_string(), "".to_string()))
-}
+    #[test]
+    fn test_format() {
+        let mut wifi = Wifi::new(
+            false,
+            10,
+            2,
+            true,
+            SparklineLayout::Stacked,
+            false,
+            Some("{icon} {ssid} {signal}% {dbm}dBm {band} {bitrate} ↓{down} ↑{up}".to_string()),
+        );
+        wifi.link = Some(Link {
+            ssid: Some("Home".to_string()),
+            signal: Some(Signal {
+                interface: "wlan0".to_string(),
+                quality: 77,
+                level: -56,
+            }),
+            frequency: Some(5180),
+            bitrate: Some(866_700_000),
+        });
+        wifi.rates = Some(Rates {
+            down: 1536.0 * 1024.0,
+            up: 640.0,
+        });
+        wifi.refresh_content();
+        assert_eq!(
+            wifi.cached_span_content,
+            "󰤥 Home 77% -56dBm 5 GHz 867 Mb/s ↓1.5 MiB/s ↑640 B/s"
+        );
 
-fn get_network_usage() -> Option<u64> {
-    let content = std::fs::read_to_string("/proc/net/dev").ok()?;
-
-    for line in content.lines().skip(2) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 10 {
-            let interface = parts[0].trim_end_matches(':');
-            // Look for wireless interfaces (common prefixes)
-            if interface.starts_with("wlan")
-                || interface.starts_with("wifi")
-                || interface.starts_with("wl")
-            {
-                // Return sum of received and transmitted bytes
-                let rx_bytes: u64 = parts[1].parse().ok()?;
-                let tx_bytes: u64 = parts[9].parse().ok()?;
-                return Some(rx_bytes + tx_bytes);
-            }
-        }
+        wifi.format = None;
+        wifi.link = None;
+        wifi.refresh_content();
+        assert_eq!(wifi.cached_span_content, "󰤮 Off");
     }
 
-    None
+    #[test]
+    fn test_peaks() {
+        let mut wifi = Wifi::new(true, 4, 2, true, SparklineLayout::Stacked, true, None);
+        wifi.sparkline.update(3 * 1024 * 1024, 2048);
+        wifi.sparkline.update(1024, 512);
+        wifi.sparkline_content = wifi.sparkline.render();
+        wifi.refresh_content();
+
+        let rendered: String = wifi
+            .render_as_spans(false)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(rendered, "󰤮   █▄ ↓0 B/s ↑0 B/s peak ↓3.0 MiB/s ↑2.0 KiB/s");
+    }
 }
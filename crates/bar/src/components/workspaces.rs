@@ -1,125 +1,388 @@
 use ratatui::{prelude::Stylize, style::Color, text::Span};
-use serde::Deserialize;
-use std::process::Command;
+use std::collections::HashMap;
 
-use crate::logging;
+use crate::window_manager::{self, WindowManager};
+use std::sync::Arc;
 
-#[derive(Deserialize, Debug)]
-struct Workspace {
-    id: i32,
+const RAINBOW_COLORS: [Color; 7] = [
+    Color::Red,      // 1
+    Color::Yellow,   // 2
+    Color::Green,    // 3
+    Color::Cyan,     // 4
+    Color::Blue,     // 5
+    Color::Magenta,  // 6
+    Color::LightRed, // 7
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    /// Text shown in the bar: the configured label, or the name
+    pub label: String,
+    /// Currently shown on its output
+    pub active: bool,
+    /// Persistent placeholder for a workspace that doesn't exist yet
+    pub placeholder: bool,
+    /// Holds a window demanding attention
+    pub urgent: bool,
+    pub window_count: usize,
+    number: Option<i32>,
+}
+
+/// How to show the number of windows on each workspace
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum WindowIndicator {
+    #[default]
+    None,
+    /// Superscript count, e.g. `1³`
+    Count,
+    /// One dot per window, up to three
+    Dots,
+}
+
+impl WindowIndicator {
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value {
+            Some("count") => Self::Count,
+            Some("dots") => Self::Dots,
+            _ => Self::None,
+        }
+    }
+
+    fn render(&self, window_count: usize) -> String {
+        const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+        if window_count == 0 {
+            return String::new();
+        }
+
+        match self {
+            Self::None => String::new(),
+            Self::Count => window_count
+                .to_string()
+                .chars()
+                .filter_map(|c| c.to_digit(10).map(|d| SUPERSCRIPTS[d as usize]))
+                .collect(),
+            Self::Dots => "·".repeat(window_count.min(3)),
+        }
+    }
+}
+
+/// A special (scratchpad) workspace, shown as a separate indicator
+#[derive(Debug, Default, Clone)]
+pub struct ScratchpadInfo {
+    pub name: String,
+    pub window_count: usize,
+    /// Currently toggled on screen
+    pub visible: bool,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Workspaces {
-    pub workspaces: Vec<String>,
-    pub active_workspace: String,
+    pub workspaces: Vec<WorkspaceInfo>,
+    pub scratchpads: Vec<ScratchpadInfo>,
+    output: Option<String>,
+    labels: HashMap<String, String>,
+    persistent: Vec<String>,
+    window_indicator: WindowIndicator,
+    window_manager: Option<Arc<dyn WindowManager>>,
 }
 
 impl Workspaces {
     pub fn new() -> Self {
-        Self {
-            workspaces: get_workspaces().unwrap_or_default(),
-            active_workspace: get_active_workspace().unwrap_or_default(),
-        }
+        Self::with_config(None, HashMap::new(), Vec::new(), WindowIndicator::None)
+    }
+
+    pub fn with_config(
+        monitor: Option<String>,
+        labels: HashMap<String, String>,
+        persistent: Vec<String>,
+        window_indicator: WindowIndicator,
+    ) -> Self {
+        let window_manager = window_manager::subscribe();
+        let mut workspaces = Self {
+            workspaces: Vec::new(),
+            scratchpads: Vec::new(),
+            output: window_manager::resolve_output(monitor.as_deref()),
+            labels,
+            persistent,
+            window_indicator,
+            window_manager,
+        };
+        workspaces.update();
+        workspaces
     }
 
     pub fn update(&mut self) {
-        self.workspaces = get_workspaces().unwrap_or_default();
-        self.active_workspace = get_active_workspace().unwrap_or_default();
+        let (workspaces, scratchpads) =
+            get_workspaces(self.window_manager.as_deref(), self.output.as_deref())
+                .unwrap_or_default();
+        self.set_workspaces(workspaces);
+        self.scratchpads = scratchpads;
+    }
+
+    /// Merge in persistent placeholders, sort and apply configured labels
+    fn set_workspaces(&mut self, workspaces: Vec<WorkspaceInfo>) {
+        self.workspaces = workspaces;
+
+        // Add placeholders for persistent workspaces that don't currently exist
+        for name in &self.persistent {
+            if !self.workspaces.iter().any(|w| &w.name == name) {
+                self.workspaces.push(WorkspaceInfo {
+                    name: name.clone(),
+                    label: String::new(),
+                    active: false,
+                    placeholder: true,
+                    urgent: false,
+                    window_count: 0,
+                    number: name.parse().ok(),
+                });
+            }
+        }
+        self.workspaces
+            .sort_by(|a, b| sort_key(a).cmp(&sort_key(b)).then(a.name.cmp(&b.name)));
+
+        for workspace in &mut self.workspaces {
+            workspace.label = label_for(&self.labels, workspace);
+        }
     }
 
     pub fn render_as_spans(&self, colorize: bool) -> Vec<Span<'_>> {
-        let rainbow_colors = [
-            Color::Red,      // 1
-            Color::Yellow,   // 2
-            Color::Green,    // 3
-            Color::Cyan,     // 4
-            Color::Blue,     // 5
-            Color::Magenta,  // 6
-            Color::LightRed, // 7
-        ];
+        let scratchpads = self.scratchpads.iter().map(|s| {
+            let span = Span::raw(format!(" 󰘓 {} {} ", s.name, s.window_count));
+            match (s.visible, colorize) {
+                (true, true) => span.bg(Color::Magenta).fg(Color::Black),
+                (true, false) => span.bg(Color::White).fg(Color::Black),
+                (false, true) => span.fg(Color::Magenta),
+                (false, false) => span.fg(Color::DarkGray),
+            }
+        });
 
         self.workspaces
             .iter()
             .map(|w| {
-                if w == &self.active_workspace {
+                let span = Span::raw(format!(
+                    " {}{} ",
+                    w.label,
+                    self.window_indicator.render(w.window_count)
+                ));
+                if w.urgent && !w.active {
+                    // Urgent until visited, regardless of colorize
+                    span.bg(Color::Red).fg(Color::White).bold().slow_blink()
+                } else if w.active {
                     if colorize {
-                        if let Ok(workspace_num) = w.parse::<usize>() {
-                            let color_index = (workspace_num - 1) % rainbow_colors.len();
-                            let bg_color = rainbow_colors[color_index];
-                            // Use black text for better readability on all colored backgrounds
-                            Span::raw(format!(" {} ", w)).bg(bg_color).fg(Color::Black)
-                        } else {
-                            // Fallback for non-numeric workspace names
-                            Span::raw(format!(" {} ", w))
-                                .bg(Color::White)
-                                .fg(Color::Black)
-                        }
+                        // Use black text for better readability on all colored backgrounds
+                        span.bg(workspace_color(w)).fg(Color::Black)
                     } else {
                         // Non-colorized mode: black text on white background for active workspace
-                        Span::raw(format!(" {} ", w))
-                            .bg(Color::White)
-                            .fg(Color::Black)
+                        span.bg(Color::White).fg(Color::Black)
                     }
+                } else if w.placeholder {
+                    // Empty persistent workspace: dimmed
+                    span.fg(Color::DarkGray)
                 } else if colorize {
-                    if let Ok(workspace_num) = w.parse::<usize>() {
-                        let color_index = (workspace_num - 1) % rainbow_colors.len();
-                        let color = rainbow_colors[color_index];
-                        Span::raw(format!(" {} ", w)).fg(color)
-                    } else {
-                        Span::raw(format!(" {} ", w))
-                    }
+                    span.fg(workspace_color(w))
                 } else {
                     // Non-colorized mode: white text for non-active workspaces
-                    Span::raw(format!(" {} ", w)).fg(Color::White)
+                    span.fg(Color::White)
                 }
             })
+            .chain(scratchpads)
             .collect::<Vec<Span>>()
     }
 }
 
-fn get_workspaces() -> Option<Vec<String>> {
-    let output = Command::new("hyprctl")
-        .args(["workspaces", "-j"])
-        .output()
-        .expect("failed to get workspaces");
-
-    if output.status.success() {
-        let stdout = str::from_utf8(&output.stdout).unwrap();
-        let json: Vec<Workspace> =
-            serde_json::from_str(stdout).expect("failed to parse workspaces");
-
-        let mut workspaces: Vec<String> = json.iter().map(|j| j.id.to_string()).collect();
-        workspaces.sort_by_key(|w| w.parse::<i32>().unwrap_or(0));
-        return Some(workspaces);
-    } else {
-        logging::log_component_error(
-            "WORKSPACES",
-            str::from_utf8(&output.stderr).unwrap_or("unknown error"),
+/// Look up a configured label by workspace name, then by number
+fn label_for(labels: &HashMap<String, String>, workspace: &WorkspaceInfo) -> String {
+    labels
+        .get(&workspace.name)
+        .or_else(|| workspace.number.and_then(|n| labels.get(&n.to_string())))
+        .cloned()
+        .unwrap_or_else(|| workspace.name.clone())
+}
+
+/// Numbered workspaces first, in order, followed by named ones
+fn sort_key(workspace: &WorkspaceInfo) -> i32 {
+    workspace.number.unwrap_or(i32::MAX)
+}
+
+/// Rainbow color by workspace number, or a stable color derived from the name
+fn workspace_color(workspace: &WorkspaceInfo) -> Color {
+    let index = match workspace.number {
+        Some(number) => (number as i64 - 1).rem_euclid(RAINBOW_COLORS.len() as i64) as usize,
+        None => {
+            workspace.name.bytes().fold(0usize, |acc, b| {
+                acc.wrapping_mul(31).wrapping_add(b as usize)
+            }) % RAINBOW_COLORS.len()
+        }
+    };
+    RAINBOW_COLORS[index]
+}
+
+/// Workspaces and non-empty scratchpads on the given output, or on all outputs
+fn get_workspaces(
+    window_manager: Option<&dyn WindowManager>,
+    output: Option<&str>,
+) -> Option<(Vec<WorkspaceInfo>, Vec<ScratchpadInfo>)> {
+    let state = window_manager?.state();
+
+    let (special, regular): (Vec<_>, Vec<_>) = state
+        .workspaces
+        .iter()
+        // Scratchpads not tied to an output (e.g. sway's) show everywhere
+        .filter(|w| state.on_output(w.id, output) || (w.special && w.output.is_empty()))
+        .partition(|w| w.special);
+
+    let workspaces = regular
+        .iter()
+        .map(|w| WorkspaceInfo {
+            name: w.name.clone(),
+            label: String::new(),
+            active: w.visible,
+            placeholder: false,
+            urgent: state.windows_on(w.id).any(|window| window.urgent),
+            window_count: state.windows_on(w.id).count(),
+            // Named workspaces (e.g. sway's "2:web" or niri's named ones) still sort by number
+            number: w.name.parse().ok().or((w.number > 0).then_some(w.number)),
+        })
+        .collect();
+
+    let mut scratchpads: Vec<ScratchpadInfo> = special
+        .iter()
+        .map(|w| ScratchpadInfo {
+            name: scratchpad_name(&w.name),
+            window_count: state.windows_on(w.id).count(),
+            visible: w.visible,
+        })
+        .filter(|s| s.visible || s.window_count > 0)
+        .collect();
+    scratchpads.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Some((workspaces, scratchpads))
+}
+
+/// `special:term` → `term`; the unnamed Hyprland special workspace and sway's
+/// `__i3_scratch` are both just the scratchpad
+fn scratchpad_name(name: &str) -> String {
+    match name.strip_prefix("special:") {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "scratchpad".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(name: &str, active: bool) -> WorkspaceInfo {
+        WorkspaceInfo {
+            name: name.to_string(),
+            label: String::new(),
+            active,
+            placeholder: false,
+            urgent: false,
+            window_count: 0,
+            number: name.parse().ok(),
+        }
+    }
+
+    fn workspaces_with(
+        existing: Vec<WorkspaceInfo>,
+        labels: &[(&str, &str)],
+        persistent: &[&str],
+    ) -> Workspaces {
+        let mut workspaces = Workspaces {
+            workspaces: Vec::new(),
+            scratchpads: Vec::new(),
+            output: None,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            persistent: persistent.iter().map(|p| p.to_string()).collect(),
+            window_indicator: WindowIndicator::None,
+            window_manager: None,
+        };
+        workspaces.set_workspaces(existing);
+        workspaces
+    }
+
+    #[test]
+    fn test_labels_and_persistent_placeholders() {
+        let workspaces = workspaces_with(
+            vec![workspace("2", true), workspace("web", false)],
+            &[("1", ""), ("web", "󰖟")],
+            &["1", "2", "3"],
         );
+
+        let labels: Vec<_> = workspaces
+            .workspaces
+            .iter()
+            .map(|w| w.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["", "2", "3", "󰖟"]);
+
+        let placeholders: Vec<_> = workspaces
+            .workspaces
+            .iter()
+            .map(|w| w.placeholder)
+            .collect();
+        assert_eq!(placeholders, vec![true, false, true, false]);
+
+        let spans = workspaces.render_as_spans(true);
+        assert_eq!(spans[0].style.fg, Some(Color::DarkGray));
+        assert_eq!(spans[1].style.bg, Some(Color::Yellow));
     }
 
-    None
-}
+    #[test]
+    fn test_urgent_and_window_indicator() {
+        let mut urgent = workspace("3", false);
+        urgent.urgent = true;
+        urgent.window_count = 12;
+        let mut workspaces = workspaces_with(vec![urgent], &[], &[]);
+        workspaces.window_indicator = WindowIndicator::Count;
 
-fn get_active_workspace() -> Option<String> {
-    let output = Command::new("hyprctl")
-        .args(["activeworkspace", "-j"])
-        .output()
-        .expect("failed to get active workspace");
-
-    if output.status.success() {
-        let stdout = str::from_utf8(&output.stdout).unwrap();
-        let json: Workspace =
-            serde_json::from_str(stdout).expect("failed to parse active workspace");
-
-        return Some(json.id.clone().to_string());
-    } else {
-        logging::log_component_error(
-            "WORKSPACES",
-            str::from_utf8(&output.stderr).unwrap_or("unknown error"),
+        let spans = workspaces.render_as_spans(false);
+        assert_eq!(spans[0].content, " 3¹² ");
+        assert_eq!(spans[0].style.bg, Some(Color::Red));
+        assert!(
+            spans[0]
+                .style
+                .add_modifier
+                .contains(ratatui::style::Modifier::SLOW_BLINK)
         );
+
+        assert_eq!(WindowIndicator::Dots.render(5), "···");
+        assert_eq!(WindowIndicator::Dots.render(0), "");
+    }
+
+    #[test]
+    fn test_scratchpads_render_after_workspaces() {
+        let mut workspaces = workspaces_with(vec![workspace("1", true)], &[], &[]);
+        workspaces.scratchpads = vec![ScratchpadInfo {
+            name: scratchpad_name("special:term"),
+            window_count: 2,
+            visible: true,
+        }];
+
+        let spans = workspaces.render_as_spans(true);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[1].content, " 󰘓 term 2 ");
+        assert_eq!(spans[1].style.bg, Some(Color::Magenta));
+
+        assert_eq!(scratchpad_name("special"), "scratchpad");
+        assert_eq!(scratchpad_name("__i3_scratch"), "scratchpad");
     }
 
-    None
+    #[test]
+    fn test_workspace_colors() {
+        assert_eq!(workspace_color(&workspace("1", false)), Color::Red);
+        assert_eq!(workspace_color(&workspace("8", false)), Color::Red);
+        // Zero and negative numbers must not underflow
+        assert_eq!(workspace_color(&workspace("0", false)), Color::LightRed);
+        assert_eq!(
+            workspace_color(&workspace("chat", false)),
+            workspace_color(&workspace("chat", true))
+        );
+    }
 }
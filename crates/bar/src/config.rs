@@ -1,11 +1,12 @@
 use crate::time_utils;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ComponentConfig {
     String(String),
-    Object(ComponentOptions),
+    Object(Box<ComponentOptions>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,7 +21,33 @@ pub struct ComponentOptions {
     #[serde(default)]
     pub sparkline_logarithmic: Option<bool>,
     #[serde(default)]
+    pub sparkline_layout: Option<String>,
+    #[serde(default)]
+    pub sparkline_peaks: Option<bool>,
+    #[serde(default)]
     pub socket_path: Option<String>,
+    #[serde(default)]
+    pub monitor: Option<String>,
+    #[serde(default)]
+    pub workspace_labels: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub persistent_workspaces: Option<Vec<String>>,
+    #[serde(default)]
+    pub window_indicator: Option<String>,
+    #[serde(default)]
+    pub window_mode: Option<String>,
+    #[serde(default)]
+    pub max_windows: Option<usize>,
+    #[serde(default)]
+    pub max_title_length: Option<usize>,
+    #[serde(default)]
+    pub session: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub interface: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -169,10 +196,101 @@ impl ComponentConfig {
         }
     }
 
+    pub fn sparkline_layout(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.sparkline_layout.clone(),
+        }
+    }
+
+    pub fn sparkline_peaks(&self) -> Option<bool> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.sparkline_peaks,
+        }
+    }
+
     pub fn socket_path(&self) -> Option<String> {
         match self {
             ComponentConfig::String(_) => None,
             ComponentConfig::Object(options) => options.socket_path.clone(),
         }
     }
+
+    pub fn monitor(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.monitor.clone(),
+        }
+    }
+
+    pub fn workspace_labels(&self) -> Option<HashMap<String, String>> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.workspace_labels.clone(),
+        }
+    }
+
+    pub fn persistent_workspaces(&self) -> Option<Vec<String>> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.persistent_workspaces.clone(),
+        }
+    }
+
+    pub fn window_indicator(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.window_indicator.clone(),
+        }
+    }
+
+    pub fn window_mode(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.window_mode.clone(),
+        }
+    }
+
+    pub fn max_windows(&self) -> Option<usize> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.max_windows,
+        }
+    }
+
+    pub fn max_title_length(&self) -> Option<usize> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.max_title_length,
+        }
+    }
+
+    pub fn session(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.session.clone(),
+        }
+    }
+
+    pub fn format(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.format.clone(),
+        }
+    }
+
+    pub fn device(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.device.clone(),
+        }
+    }
+
+    pub fn interface(&self) -> Option<String> {
+        match self {
+            ComponentConfig::String(_) => None,
+            ComponentConfig::Object(options) => options.interface.clone(),
+        }
+    }
 }
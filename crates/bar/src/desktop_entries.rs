@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the `applications` directories are checked for installed or removed apps
+const RESCAN_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Scanned on first use and again once stale or invalidated
+static INDEX: Mutex<Option<CachedIndex>> = Mutex::new(None);
+
+/// Brand names found anywhere in an icon or app name, and their Nerd Font glyph
+const BRAND_GLYPHS: &[(&str, &str)] = &[
+    ("firefox", "󰈹"),
+    ("chrom", "󰊯"),
+    ("vscode", "󰨞"),
+    ("thunderbird", "󰇰"),
+    ("discord", "󰙯"),
+    ("telegram", "󰈨"),
+    ("signal-desktop", "󰍦"),
+    ("spotify", "󰓇"),
+    ("vlc", "󰕼"),
+    ("gimp", "󰏘"),
+    ("nautilus", "󰉋"),
+    ("steam", "󰓓"),
+];
+
+/// Generic words, only matched as whole `-`, `.`, `_` or space separated parts of an icon or
+/// app name, so `web` in `camera-web` or `code` in `barcode` don't count; checked in order
+const WORD_GLYPHS: &[(&str, &str)] = &[
+    ("browser", "󰖟"),
+    ("terminal", "󰆍"),
+    ("console", "󰆍"),
+    ("code", "󰨞"),
+    ("editor", "󰏫"),
+    ("mail", "󰇰"),
+    ("chat", "󰭹"),
+    ("music", "󰓇"),
+    ("audio", "󰝚"),
+    ("video", "󰐹"),
+    ("player", "󰐹"),
+    ("image", "󰋩"),
+    ("photo", "󰋩"),
+    ("photos", "󰋩"),
+    ("pdf", "󰈦"),
+    ("document", "󰈙"),
+    ("office", "󰏪"),
+    ("calc", "󰃬"),
+    ("calculator", "󰃬"),
+    ("folder", "󰉋"),
+    ("files", "󰉋"),
+    ("file-manager", "󰉋"),
+    ("game", "󰊴"),
+    ("games", "󰊴"),
+    ("settings", "󰒓"),
+    ("preferences", "󰒓"),
+];
+
+/// The parts of a `.desktop` file's `[Desktop Entry]` group used to identify windows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopEntry {
+    /// Desktop file id without the `.desktop` suffix, e.g. `org.gnome.Nautilus`
+    pub id: String,
+    pub name: String,
+    /// Icon theme name, e.g. `org.gnome.Nautilus` or `utilities-terminal`
+    pub icon: Option<String>,
+    pub startup_wm_class: Option<String>,
+}
+
+impl DesktopEntry {
+    pub fn parse(id: &str, content: &str) -> Option<Self> {
+        let mut entry = Self {
+            id: id.to_string(),
+            ..Self::default()
+        };
+        let mut in_entry = false;
+
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=').filter(|_| in_entry) else {
+                continue;
+            };
+            // Localized keys such as `Name[de]` are skipped
+            match key.trim() {
+                "Name" => entry.name = value.trim().to_string(),
+                "Icon" => entry.icon = Some(value.trim().to_string()),
+                "StartupWMClass" => entry.startup_wm_class = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+
+        (!entry.name.is_empty()).then_some(entry)
+    }
+
+    /// Nerd Font glyph for the entry's icon name, falling back to its name
+    pub fn glyph(&self) -> Option<&'static str> {
+        let icon = self.icon.as_deref().unwrap_or_default().to_lowercase();
+        let name = self.name.to_lowercase();
+        [icon, name]
+            .iter()
+            .filter(|candidate| !candidate.is_empty())
+            .find_map(|candidate| {
+                let words = format!("-{}-", candidate.replace(['.', '_', ' '], "-"));
+                BRAND_GLYPHS
+                    .iter()
+                    .find(|(brand, _)| candidate.contains(brand))
+                    .or_else(|| {
+                        WORD_GLYPHS
+                            .iter()
+                            .find(|(word, _)| words.contains(&format!("-{}-", word)))
+                    })
+                    .map(|(_, glyph)| *glyph)
+            })
+    }
+}
+
+/// Desktop entries found under the XDG data directories
+#[derive(Debug, Default)]
+pub struct DesktopIndex {
+    entries: Vec<DesktopEntry>,
+}
+
+impl DesktopIndex {
+    /// Read `applications/*.desktop` in each data dir; earlier dirs take precedence
+    pub fn scan(data_dirs: &[PathBuf]) -> Self {
+        let mut entries: Vec<DesktopEntry> = Vec::new();
+        for dir in data_dirs {
+            let mut found = Vec::new();
+            collect_entries(&dir.join("applications"), "", &mut found);
+            for entry in found {
+                if !entries.iter().any(|e| e.id == entry.id) {
+                    entries.push(entry);
+                }
+            }
+        }
+        Self { entries }
+    }
+
+    /// Entry for a window class, matched against `StartupWMClass`, then the desktop file id
+    pub fn find(&self, class: &str) -> Option<&DesktopEntry> {
+        let class = class.to_lowercase();
+        if class.is_empty() {
+            return None;
+        }
+
+        self.entries
+            .iter()
+            .find(|e| {
+                e.startup_wm_class
+                    .as_deref()
+                    .is_some_and(|c| c.to_lowercase() == class)
+            })
+            .or_else(|| self.entries.iter().find(|e| e.id.to_lowercase() == class))
+            // Reverse-DNS ids such as `org.gnome.Nautilus` for class `nautilus`
+            .or_else(|| {
+                self.entries.iter().find(|e| {
+                    e.id.rsplit('.')
+                        .next()
+                        .is_some_and(|last| last.to_lowercase() == class)
+                })
+            })
+    }
+}
+
+/// Desktop file ids in subdirectories join the path with `-`, e.g. `kde4-kate`
+fn collect_entries(dir: &Path, prefix: &str, entries: &mut Vec<DesktopEntry>) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for item in read_dir.flatten() {
+        let path = item.path();
+        let file_name = item.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            collect_entries(&path, &format!("{}{}-", prefix, file_name), entries);
+        } else if let Some(stem) = file_name.strip_suffix(".desktop")
+            && let Ok(content) = std::fs::read_to_string(&path)
+            && let Some(entry) = DesktopEntry::parse(&format!("{}{}", prefix, stem), &content)
+        {
+            entries.push(entry);
+        }
+    }
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, with the spec's defaults
+fn data_dirs() -> Vec<PathBuf> {
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            format!("{}/.local/share", home)
+        });
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    std::iter::once(data_home.as_str())
+        .chain(data_dirs.split(':'))
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// The installed desktop entries and the glyphs resolved from them so far
+struct CachedIndex {
+    index: DesktopIndex,
+    data_dirs: Vec<PathBuf>,
+    /// Modification time of each `applications` directory when scanned
+    modified: Vec<Option<SystemTime>>,
+    checked_at: Instant,
+    /// Glyphs resolved per window class, so each class is only looked up once per scan
+    glyphs: HashMap<String, Option<String>>,
+}
+
+impl CachedIndex {
+    fn scan(data_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            index: DesktopIndex::scan(&data_dirs),
+            modified: applications_modified(&data_dirs),
+            data_dirs,
+            checked_at: Instant::now(),
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Whether an `applications` directory changed since the scan, checked now and then
+    fn is_stale(&mut self) -> bool {
+        if self.checked_at.elapsed() < RESCAN_CHECK_INTERVAL {
+            return false;
+        }
+        self.checked_at = Instant::now();
+        applications_modified(&self.data_dirs) != self.modified
+    }
+}
+
+/// Installing or removing a `.desktop` file touches its directory
+fn applications_modified(data_dirs: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    data_dirs
+        .iter()
+        .map(|dir| {
+            std::fs::metadata(dir.join("applications"))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+/// Rescan the desktop entries on the next lookup
+pub fn invalidate() {
+    if let Ok(mut index) = INDEX.lock() {
+        *index = None;
+    }
+}
+
+/// Glyph for a window class from its installed desktop entry, cached across updates
+pub fn glyph_for_class(class: &str) -> Option<String> {
+    let mut cached = INDEX.lock().ok()?;
+    if cached.as_mut().is_none_or(CachedIndex::is_stale) {
+        *cached = Some(CachedIndex::scan(data_dirs()));
+    }
+
+    let CachedIndex { index, glyphs, .. } = cached.as_mut()?;
+    glyphs
+        .entry(class.to_string())
+        .or_insert_with(|| index.find(class)?.glyph().map(str::to_string))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let entry = DesktopEntry::parse(
+            "org.gnome.Nautilus",
+            "[Desktop Entry]\nName=Files\nName[de]=Dateien\nIcon=org.gnome.Nautilus\n\
+             StartupWMClass=org.gnome.Nautilus\n\n[Desktop Action new-window]\nName=New Window\n",
+        )
+        .unwrap();
+        assert_eq!(entry.name, "Files");
+        assert_eq!(entry.icon.as_deref(), Some("org.gnome.Nautilus"));
+        assert_eq!(entry.glyph(), Some("󰉋"));
+
+        assert!(DesktopEntry::parse("empty", "[Desktop Action x]\nName=X\n").is_none());
+    }
+
+    #[test]
+    fn test_glyph_words() {
+        let glyph = |icon: &str| {
+            DesktopEntry {
+                name: "App".to_string(),
+                icon: Some(icon.to_string()),
+                ..DesktopEntry::default()
+            }
+            .glyph()
+        };
+        assert_eq!(glyph("system-file-manager"), glyph("org.gnome.Nautilus"));
+        assert_eq!(glyph("com.visualstudio.code"), glyph("vscode"));
+        assert!(glyph("com.visualstudio.code").is_some());
+        assert_eq!(glyph("camera-web"), None);
+        assert_eq!(glyph("barcode-scanner"), None);
+        assert_eq!(glyph("system-monitor"), None);
+    }
+
+    #[test]
+    fn test_scan_and_find() {
+        let home = tempfile::tempdir().unwrap();
+        let system = tempfile::tempdir().unwrap();
+        let write = |dir: &Path, name: &str, content: &str| {
+            let path = dir.join("applications").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+
+        write(
+            system.path(),
+            "org.example.Mailer.desktop",
+            "[Desktop Entry]\nName=Mailer\nIcon=internet-mail\n",
+        );
+        write(
+            system.path(),
+            "zed.desktop",
+            "[Desktop Entry]\nName=Zed\nIcon=zed\nStartupWMClass=dev.zed.Zed\n",
+        );
+        write(
+            system.path(),
+            "kde4/kcalc.desktop",
+            "[Desktop Entry]\nName=KCalc\nIcon=accessories-calculator\n",
+        );
+        // The user's copy overrides the system one
+        write(
+            home.path(),
+            "zed.desktop",
+            "[Desktop Entry]\nName=Zed\nIcon=text-editor\nStartupWMClass=dev.zed.Zed\n",
+        );
+
+        let index = DesktopIndex::scan(&[home.path().to_path_buf(), system.path().to_path_buf()]);
+
+        assert_eq!(index.find("mailer").unwrap().glyph(), Some("󰇰"));
+        assert_eq!(index.find("dev.zed.Zed").unwrap().glyph(), Some("󰏫"));
+        assert_eq!(index.find("kde4-kcalc").unwrap().name, "KCalc");
+        assert!(index.find("unknown").is_none());
+        assert!(index.find("").is_none());
+    }
+}
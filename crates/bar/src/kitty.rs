@@ -0,0 +1,499 @@
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const REQUEST_PREFIX: &[u8] = b"\x1bP@kitty-cmd";
+const TERMINATOR: &[u8] = b"\x1b\\";
+/// Oldest kitty version whose remote control commands we rely on
+const PROTOCOL_VERSION: [u32; 3] = [0, 26, 0];
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize, Debug)]
+pub struct KittyOsWindow {
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_focused: bool,
+    #[serde(default)]
+    pub last_focused: bool,
+    pub tabs: Vec<KittyTab>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KittyTab {
+    #[serde(default)]
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub is_active: bool,
+    /// A window in the tab rang the bell
+    #[serde(default)]
+    pub needs_attention: bool,
+    /// Output arrived since the tab was last focused
+    #[serde(default)]
+    pub has_activity_since_last_focus: bool,
+    #[serde(default)]
+    pub windows: Vec<KittyWindow>,
+}
+
+impl KittyTab {
+    /// The window last focused within the tab
+    pub fn active_window(&self) -> Option<&KittyWindow> {
+        self.windows
+            .iter()
+            .find(|w| w.is_active || w.is_focused)
+            .or(self.windows.first())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KittyWindow {
+    #[serde(default)]
+    pub id: u64,
+    /// PID of the window's shell (or other initial program)
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_focused: bool,
+    #[serde(default)]
+    pub foreground_processes: Vec<KittyProcess>,
+}
+
+impl KittyWindow {
+    /// The foreground program, unless it's the window's own shell sitting at a prompt
+    pub fn foreground_process(&self) -> Option<&KittyProcess> {
+        self.foreground_processes
+            .iter()
+            .find(|p| p.pid.is_none() || p.pid != self.pid)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KittyProcess {
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub cmdline: Vec<String>,
+}
+
+impl KittyProcess {
+    /// Command line with the program's directory stripped, e.g. `nvim src/main.rs`
+    pub fn command(&self) -> Option<String> {
+        let (program, args) = self.cmdline.split_first()?;
+        let program = program.rsplit('/').next().unwrap_or(program);
+        Some(
+            std::iter::once(program)
+                .chain(args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Response {
+    ok: bool,
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Client for kitty's remote control protocol, keeping its socket connection open
+/// between requests
+#[derive(Debug)]
+pub struct KittyClient {
+    socket_path: PathBuf,
+    stream: Option<UnixStream>,
+}
+
+impl KittyClient {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            stream: None,
+        }
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Send a command such as `ls` and return its response data.
+    ///
+    /// A stale connection (e.g. after kitty closed it) is replaced and the request retried once.
+    pub fn request(
+        &mut self,
+        cmd: &str,
+        payload: Option<serde_json::Value>,
+    ) -> color_eyre::Result<serde_json::Value> {
+        let mut message = serde_json::json!({
+            "cmd": cmd,
+            "version": PROTOCOL_VERSION,
+            "no_response": false,
+        });
+        if let Some(payload) = payload {
+            message["payload"] = payload;
+        }
+        let mut bytes = REQUEST_PREFIX.to_vec();
+        bytes.extend(serde_json::to_vec(&message)?);
+        bytes.extend(TERMINATOR);
+
+        let reused = self.stream.is_some();
+        let response = match self.send(&bytes) {
+            Err(_) if reused => self.send(&bytes),
+            result => result,
+        }?;
+
+        if !response.ok {
+            return Err(color_eyre::eyre::eyre!(
+                "kitty {}: {}",
+                cmd,
+                response.error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+        Ok(response.data)
+    }
+
+    /// OS windows with their tabs
+    pub fn ls(&mut self) -> color_eyre::Result<Vec<KittyOsWindow>> {
+        // Newer kitty versions send the listing as a JSON-encoded string
+        match self.request("ls", None)? {
+            serde_json::Value::String(data) => Ok(serde_json::from_str(&data)?),
+            data => Ok(serde_json::from_value(data)?),
+        }
+    }
+
+    /// Switch to the tab with the given id
+    pub fn focus_tab(&mut self, id: u64) -> color_eyre::Result<()> {
+        self.request(
+            "focus-tab",
+            Some(serde_json::json!({ "match": format!("id:{}", id) })),
+        )?;
+        Ok(())
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> std::io::Result<Response> {
+        let result = self.exchange(bytes);
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
+    }
+
+    fn exchange(&mut self, bytes: &[u8]) -> std::io::Result<Response> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                let stream = connect(&self.socket_path)?;
+                stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+                self.stream.insert(stream)
+            }
+        };
+        stream.write_all(bytes)?;
+
+        let mut reply = Vec::new();
+        let mut buffer = [0u8; 8192];
+        while !reply.ends_with(TERMINATOR) {
+            let read = stream.read(&mut buffer)?;
+            if read == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            reply.extend_from_slice(&buffer[..read]);
+        }
+
+        let body = reply
+            .strip_prefix(REQUEST_PREFIX)
+            .and_then(|r| r.strip_suffix(TERMINATOR))
+            .ok_or_else(|| std::io::Error::other("malformed kitty response"))?;
+        serde_json::from_slice(body).map_err(std::io::Error::other)
+    }
+}
+
+/// Connect to a socket path, or to an abstract socket given as `@name`
+fn connect(socket_path: &Path) -> std::io::Result<UnixStream> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::SocketAddr;
+
+    match socket_path.as_os_str().as_bytes().strip_prefix(b"@") {
+        Some(name) => UnixStream::connect_addr(&SocketAddr::from_abstract_name(name)?),
+        None => UnixStream::connect(socket_path),
+    }
+}
+
+/// Finds the remote control socket of a kitty instance from its PID
+#[derive(Debug, Clone)]
+pub struct SocketDiscovery {
+    /// Path with `{pid}` substituted, or a fixed path; tried before anything else
+    template: Option<String>,
+    proc_root: PathBuf,
+    /// Directories searched for `kitty-*-<pid>` sockets
+    search_dirs: Vec<PathBuf>,
+}
+
+impl SocketDiscovery {
+    pub fn new(template: Option<String>) -> Self {
+        let mut search_dirs = vec![PathBuf::from("/tmp")];
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+            search_dirs.push(PathBuf::from(runtime_dir));
+        }
+        Self {
+            template,
+            proc_root: PathBuf::from("/proc"),
+            search_dirs,
+        }
+    }
+
+    /// Socket for the kitty process `pid`, if one can be found
+    pub fn find(&self, pid: u32) -> Option<PathBuf> {
+        if let Some(template) = &self.template {
+            return Some(PathBuf::from(template.replace("{pid}", &pid.to_string())));
+        }
+
+        self.find_in_environ(pid)
+            .or_else(|| self.find_in_search_dirs(pid))
+    }
+
+    /// `KITTY_LISTEN_ON`, as exported to the shells kitty starts (or kitty itself, when it
+    /// was launched from another kitty)
+    fn find_in_environ(&self, pid: u32) -> Option<PathBuf> {
+        std::iter::once(pid)
+            .chain(self.children(pid))
+            .find_map(|pid| {
+                let environ =
+                    std::fs::read(self.proc_root.join(pid.to_string()).join("environ")).ok()?;
+                environ
+                    .split(|b| *b == 0)
+                    .find_map(|var| var.strip_prefix(b"KITTY_LISTEN_ON=unix:"))
+                    .map(|path| PathBuf::from(String::from_utf8_lossy(path).to_string()))
+            })
+    }
+
+    fn children(&self, pid: u32) -> Vec<u32> {
+        let Ok(tasks) = std::fs::read_dir(self.proc_root.join(pid.to_string()).join("task")) else {
+            return Vec::new();
+        };
+        tasks
+            .flatten()
+            .filter_map(|task| std::fs::read_to_string(task.path().join("children")).ok())
+            .flat_map(|children| {
+                children
+                    .split_whitespace()
+                    .filter_map(|c| c.parse().ok())
+                    .collect::<Vec<u32>>()
+            })
+            .collect()
+    }
+
+    /// kitty appends `-<pid>` to unix sockets given with `--listen-on`, e.g. `/tmp/kitty-1234`
+    fn find_in_search_dirs(&self, pid: u32) -> Option<PathBuf> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let suffix = format!("-{}", pid);
+        self.search_dirs.iter().find_map(|dir| {
+            std::fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_socket = entry.file_type().is_ok_and(|t| t.is_socket());
+                (is_socket && name.starts_with("kitty") && name.ends_with(&suffix))
+                    .then(|| entry.path())
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    const LS: &str = r#"[{"is_focused":true,"tabs":[
+        {"id":1,"title":"nvim","is_active":true,"windows":[{"id":1,"pid":100,"is_active":true,
+            "foreground_processes":[{"pid":101,"cmdline":["/usr/bin/nvim","src/main.rs"]}]}]},
+        {"id":2,"title":"zsh","is_active":false,"needs_attention":true,"windows":[{"id":2,"pid":200,
+            "foreground_processes":[{"pid":200,"cmdline":["-zsh"]}]}]}
+    ]}]"#;
+
+    /// Read one `kitty-cmd` message from the stream, or `None` once it's closed
+    fn read_command(stream: &mut UnixStream) -> Option<serde_json::Value> {
+        let mut message = Vec::new();
+        let mut byte = [0u8; 1];
+        while !message.ends_with(TERMINATOR) {
+            if stream.read(&mut byte).ok()? == 0 {
+                return None;
+            }
+            message.push(byte[0]);
+        }
+        let body = message
+            .strip_prefix(REQUEST_PREFIX)?
+            .strip_suffix(TERMINATOR)?;
+        serde_json::from_slice(body).ok()
+    }
+
+    /// Fake kitty answering `ls`, counting connections and recording received commands.
+    /// With `close_after_reply` it hangs up after every response.
+    fn spawn_fake_kitty(
+        path: &Path,
+        close_after_reply: bool,
+    ) -> (Arc<AtomicUsize>, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let listener = UnixListener::bind(path).unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let (connections_seen, commands_seen) = (connections.clone(), commands.clone());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                connections_seen.fetch_add(1, Ordering::SeqCst);
+                let commands = commands_seen.clone();
+                std::thread::spawn(move || {
+                    while let Some(command) = read_command(&mut stream) {
+                        let response = match command["cmd"].as_str() {
+                            Some("ls") => serde_json::json!({"ok": true, "data": LS}),
+                            Some("focus-tab") => serde_json::json!({"ok": true}),
+                            _ => serde_json::json!({"ok": false, "error": "Unknown command"}),
+                        };
+                        commands.lock().unwrap().push(command);
+
+                        let mut reply = REQUEST_PREFIX.to_vec();
+                        reply.extend(serde_json::to_vec(&response).unwrap());
+                        reply.extend(TERMINATOR);
+                        if stream.write_all(&reply).is_err() || close_after_reply {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (connections, commands)
+    }
+
+    #[test]
+    fn test_ls_over_persistent_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kitty.sock");
+        let (connections, commands) = spawn_fake_kitty(&path, false);
+
+        let mut client = KittyClient::new(&path);
+        for _ in 0..3 {
+            let windows = client.ls().unwrap();
+            assert_eq!(windows.len(), 1);
+            assert!(windows[0].is_focused);
+            assert_eq!(windows[0].tabs[1].title, "zsh");
+        }
+
+        let tabs = &client.ls().unwrap()[0].tabs;
+        let nvim = tabs[0]
+            .active_window()
+            .unwrap()
+            .foreground_process()
+            .unwrap();
+        assert_eq!(nvim.command().as_deref(), Some("nvim src/main.rs"));
+        // A shell at its prompt isn't a foreground program
+        assert!(
+            tabs[1]
+                .active_window()
+                .unwrap()
+                .foreground_process()
+                .is_none()
+        );
+        assert!(tabs[1].needs_attention);
+
+        client.focus_tab(2).unwrap();
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands.len(), 5);
+        assert_eq!(commands[0]["cmd"], "ls");
+        assert_eq!(commands[0]["version"], serde_json::json!([0, 26, 0]));
+        assert_eq!(commands[4]["cmd"], "focus-tab");
+        assert_eq!(commands[4]["payload"]["match"], "id:2");
+    }
+
+    #[test]
+    fn test_reconnects_when_kitty_hangs_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kitty.sock");
+        let (connections, _) = spawn_fake_kitty(&path, true);
+
+        let mut client = KittyClient::new(&path);
+        assert!(client.ls().is_ok());
+        assert!(client.ls().is_ok());
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kitty.sock");
+
+        let mut client = KittyClient::new(&path);
+        let error = client.ls().unwrap_err();
+        assert!(error.downcast_ref::<std::io::Error>().is_some());
+
+        spawn_fake_kitty(&path, false);
+        let error = client.request("bogus", None).unwrap_err();
+        assert_eq!(error.to_string(), "kitty bogus: Unknown command");
+    }
+
+    #[test]
+    fn test_socket_discovery() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let discovery = |template: Option<&str>| SocketDiscovery {
+            template: template.map(str::to_string),
+            proc_root: proc_root.path().to_path_buf(),
+            search_dirs: vec![tmp.path().to_path_buf()],
+        };
+
+        // kitty 100's shell 101 carries the socket in its environment
+        let task = proc_root.path().join("100/task/100");
+        std::fs::create_dir_all(&task).unwrap();
+        std::fs::write(task.join("children"), "101 ").unwrap();
+        std::fs::create_dir_all(proc_root.path().join("101")).unwrap();
+        std::fs::write(
+            proc_root.path().join("101/environ"),
+            b"SHELL=/bin/zsh\0KITTY_LISTEN_ON=unix:@mykitty-100\0TERM=xterm-kitty\0",
+        )
+        .unwrap();
+        assert_eq!(
+            discovery(None).find(100),
+            Some(PathBuf::from("@mykitty-100"))
+        );
+
+        // Otherwise a socket named after the PID
+        let _listener = UnixListener::bind(tmp.path().join("kitty-200")).unwrap();
+        std::fs::write(tmp.path().join("kitty-notes-200"), "").unwrap();
+        assert_eq!(
+            discovery(None).find(200),
+            Some(tmp.path().join("kitty-200"))
+        );
+        assert_eq!(discovery(None).find(300), None);
+
+        assert_eq!(
+            discovery(Some("/run/kitty/{pid}.sock")).find(300),
+            Some(PathBuf::from("/run/kitty/300.sock"))
+        );
+    }
+
+    #[test]
+    fn test_abstract_socket() {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::SocketAddr;
+
+        let name = format!("catfood-test-kitty-{}", std::process::id());
+        let listener =
+            UnixListener::bind_addr(&SocketAddr::from_abstract_name(name.as_bytes()).unwrap())
+                .unwrap();
+        assert!(connect(Path::new(&format!("@{}", name))).is_ok());
+        drop(listener);
+    }
+}
@@ -1,26 +1,42 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
     DefaultTerminal, Frame,
     layout::{Direction, Layout},
     prelude::Constraint,
 };
+use std::ffi::OsStr;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 
+pub mod apps;
+pub mod command;
 pub mod component_manager;
 pub mod components;
 pub mod config;
+pub mod desktop_entries;
+pub mod kitty;
 pub mod logging;
 pub mod lua_component;
+pub mod provider;
+pub mod pulse;
 pub mod time_utils;
+pub mod tmux;
+pub mod wakeup;
+pub mod window_manager;
+pub mod wireless;
 
 pub use component_manager::ComponentManager;
-pub use components::{LeftBar, MiddleBar, RightBar};
+pub use components::{ClickRegion, LeftBar, MiddleBar, RightBar};
+
+/// Longest the main loop sleeps without a deadline or wakeup, as a safety net
+const MAX_SLEEP: Duration = Duration::from_secs(30);
 
 /// Check if bar is already running by checking PID file
 pub fn is_bar_running() -> color_eyre::Result<bool> {
@@ -108,14 +124,15 @@ pub fn spawn_in_panel() {
 
     // Spawn kitten panel directly with proper arguments for security
     // This avoids shell injection risks from special characters in paths
-    match Command::new("kitten")
-        .arg("panel")
-        .arg("--single-instance")
-        .arg(&bar_exe)
-        .arg("--no-kitten") // Required to prevent spawning additional panels
-        .spawn()
-    {
-        Ok(_child) => {
+    // `--no-kitten` is required to prevent spawning additional panels
+    let args = [
+        OsStr::new("panel"),
+        OsStr::new("--single-instance"),
+        bar_exe.as_os_str(),
+        OsStr::new("--no-kitten"),
+    ];
+    match command::detach("kitten", &args) {
+        Ok(()) => {
             // Give panel a moment to start then exit parent
             // The child process continues running independently
             std::thread::sleep(std::time::Duration::from_millis(500));
@@ -165,7 +182,16 @@ pub fn run_bar() -> color_eyre::Result<()> {
 
     let result = rt.block_on(async {
         let terminal = ratatui::init();
+        component_manager::install_panic_hook();
+        // Clicks are routed to components; failing to capture the mouse only disables that
+        if let Err(e) = crossterm::execute!(std::io::stdout(), EnableMouseCapture) {
+            logging::log_system_error(
+                "Terminal",
+                &format!("Failed to enable mouse capture: {}", e),
+            );
+        }
         let app_result = App::new()?.run_async(terminal).await;
+        let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
         ratatui::restore();
         app_result
     });
@@ -186,6 +212,8 @@ pub struct App {
     middle_bar: MiddleBar,
     right_bar: RightBar,
     reload_rx: mpsc::Receiver<()>,
+    /// Where each component's spans were drawn last frame
+    click_regions: Vec<ClickRegion>,
 }
 
 impl App {
@@ -204,6 +232,7 @@ impl App {
             middle_bar: MiddleBar::new()?,
             right_bar: RightBar::new()?,
             reload_rx,
+            click_regions: Vec::new(),
         })
     }
 
@@ -251,12 +280,29 @@ impl App {
                 return;
             }
 
+            let apps_path = apps::user_apps_path();
+
             while let Some(event) = rx.recv().await {
                 use notify::EventKind;
 
+                let Some(path) = event.paths.first() else {
+                    continue;
+                };
+
+                // App icon overrides are looked up on every update, no component reload needed
+                if path == &apps_path
+                    && matches!(
+                        event.kind,
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                    )
+                {
+                    apps::reload();
+                    wakeup::notify();
+                    continue;
+                }
+
                 // Check if the event is related to our config file
-                if let Some(path) = event.paths.first()
-                    && path == &config_path
+                if path == &config_path
                     && matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
                     && let Err(e) = reload_tx.send(()).await
                 {
@@ -273,30 +319,53 @@ impl App {
     }
 
     /// Run the application's main loop.
+    ///
+    /// Components are updated when a background source signals a change, on input, on config
+    /// reload, or when the earliest component deadline passes; the terminal is only redrawn
+    /// when something renders differently.
     pub async fn run_async(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
+        let mut input_rx = spawn_input_reader();
+        let mut redraw = true;
+
         while self.running {
+            if self.update_components() || redraw {
+                terminal.draw(|frame| self.render(frame))?;
+                redraw = false;
+            }
+
+            let deadline = self
+                .component_manager
+                .next_update()
+                .unwrap_or_else(|| Instant::now() + MAX_SLEEP)
+                .min(Instant::now() + MAX_SLEEP);
+
             tokio::select! {
                 _ = self.reload_rx.recv() => {
                     // Handle config reload
                     if let Err(e) = self.component_manager.reload() {
                         logging::log_config_error(&format!("Failed to reload configuration: {}", e));
                     }
+                    redraw = true;
                 }
-                _ = tokio::time::sleep(Duration::from_millis(333)) => {
-                    // Normal update cycle
-                    self.update_components();
-                    terminal.draw(|frame| self.render(frame))?;
-                    self.handle_crossterm_events()?;
+                Some(event) = input_rx.recv() => {
+                    redraw = self.handle_crossterm_event(event);
                 }
+                _ = wakeup::wait() => {}
+                _ = tokio::time::sleep_until(deadline.into()) => {}
             }
         }
         Ok(())
     }
 
-    fn update_components(&mut self) {
-        if let Err(e) = self.component_manager.update() {
-            logging::log_system_error("Component Manager", &format!("{}", e));
-        }
+    /// Returns whether any component changed
+    fn update_components(&mut self) -> bool {
+        let changed = match self.component_manager.update() {
+            Ok(changed) => changed,
+            Err(e) => {
+                logging::log_system_error("Component Manager", &format!("{}", e));
+                false
+            }
+        };
         if let Err(e) = self.left_bar.update() {
             logging::log_system_error("Left Bar", &format!("{}", e));
         }
@@ -306,6 +375,7 @@ impl App {
         if let Err(e) = self.right_bar.update() {
             logging::log_system_error("Right Bar", &format!("{}", e));
         }
+        changed
     }
 
     /// Renders the user interface.
@@ -319,25 +389,32 @@ impl App {
             ])
             .split(frame.area());
 
-        self.left_bar
+        self.click_regions = self
+            .left_bar
             .render(frame, layout[0], &self.component_manager);
-        self.middle_bar
-            .render(frame, layout[1], &self.component_manager);
-        self.right_bar
-            .render(frame, layout[2], &self.component_manager);
+        self.click_regions.extend(self.middle_bar.render(
+            frame,
+            layout[1],
+            &self.component_manager,
+        ));
+        self.click_regions.extend(
+            self.right_bar
+                .render(frame, layout[2], &self.component_manager),
+        );
     }
 
-    /// Reads the crossterm events and updates the state of [`App`].
-    fn handle_crossterm_events(&mut self) -> color_eyre::Result<()> {
-        if event::poll(Duration::from_millis(333))? {
-            match event::read()? {
-                Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
-                Event::Mouse(_) => {}
-                Event::Resize(_, _) => {}
-                _ => {}
+    /// Updates the state of [`App`] from a crossterm event; returns whether to redraw.
+    fn handle_crossterm_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.on_key_event(key);
+                false
             }
+            // A click may change what the component shows, e.g. toggling mute
+            Event::Mouse(mouse) => self.on_mouse_event(mouse),
+            Event::Resize(_, _) => true,
+            _ => false,
         }
-        Ok(())
     }
 
     /// Handles the key events and updates the state of [`App`].
@@ -349,12 +426,53 @@ impl App {
         }
     }
 
+    /// Routes left clicks to the component drawn under the cursor; returns whether one was hit.
+    fn on_mouse_event(&mut self, mouse: MouseEvent) -> bool {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return false;
+        }
+        let Some(region) = self.click_regions.iter().find(|r| r.contains(mouse.column)) else {
+            return false;
+        };
+        self.component_manager
+            .handle_click(&region.component, region.span);
+        true
+    }
+
     /// Set running to false to quit the application.
     fn quit(&mut self) {
         self.running = false;
     }
 }
 
+/// Read terminal events on a blocking thread, so the main loop can wait on them alongside
+/// everything else
+fn spawn_input_reader() -> mpsc::Receiver<Event> {
+    let (tx, rx) = mpsc::channel(16);
+    std::thread::spawn(move || {
+        loop {
+            match event::read() {
+                // Mouse capture reports every motion over the bar; only clicks matter, so
+                // don't wake the main loop for them
+                Ok(Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Moved | MouseEventKind::Drag(_),
+                    ..
+                })) => {}
+                Ok(event) => {
+                    if tx.blocking_send(event).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    logging::log_system_error("Terminal", &format!("Failed to read input: {}", e));
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
 /// Get the PID file path (same as in catfood crate)
 fn get_pid_file_path() -> color_eyre::Result<PathBuf> {
     let data_dir = std::env::var("XDG_DATA_HOME").unwrap_or_else(|_| {
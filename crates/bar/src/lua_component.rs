@@ -1,6 +1,10 @@
+use crate::logging;
 use mlua::{Function, Lua, Table, Value};
 use ratatui::{prelude::Stylize, style::Color, text::Span};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone)]
 pub struct LuaComponent {
@@ -51,6 +55,11 @@ impl LuaComponent {
         Ok(())
     }
 
+    /// Scripts can change at any time, so poll them
+    pub fn next_update(&self) -> Instant {
+        Instant::now() + UPDATE_INTERVAL
+    }
+
     pub fn render_as_spans_with_colorize(&self, colorize: bool) -> Vec<Span<'_>> {
         // Try to call render function that returns (text, color)
         match self.render_fn.call::<Value>((colorize,)) {
@@ -118,6 +127,8 @@ impl LuaComponent {
 #[derive(Debug)]
 pub struct LuaComponentRegistry {
     components: HashMap<String, LuaComponent>,
+    /// Scripts that failed to load, with the reason
+    failures: HashMap<String, String>,
 }
 
 impl Default for LuaComponentRegistry {
@@ -130,6 +141,7 @@ impl LuaComponentRegistry {
     pub fn new() -> Self {
         Self {
             components: HashMap::new(),
+            failures: HashMap::new(),
         }
     }
 
@@ -143,6 +155,15 @@ impl LuaComponentRegistry {
         self.components.get(name)
     }
 
+    /// Why the script for `name` couldn't be loaded, if it failed
+    pub fn failure(&self, name: &str) -> Option<&str> {
+        self.failures.get(name).map(String::as_str)
+    }
+
+    pub fn record_failure(&mut self, name: &str, reason: &str) {
+        self.failures.insert(name.to_string(), reason.to_string());
+    }
+
     pub fn load_from_directory(&mut self, dir_path: &str) -> color_eyre::Result<()> {
         if !std::path::Path::new(dir_path).exists() {
             return Ok(());
@@ -155,7 +176,11 @@ impl LuaComponentRegistry {
             if path.extension().and_then(|s| s.to_str()) == Some("lua")
                 && let Some(name) = path.file_stem().and_then(|s| s.to_str())
             {
-                self.load_component(name, path.to_str().unwrap())?;
+                // A broken script only takes down its own component
+                if let Err(e) = self.load_component(name, path.to_str().unwrap()) {
+                    logging::log_component_error(name, &format!("Failed to load: {}", e));
+                    self.record_failure(name, &format!("{}", e));
+                }
             }
         }
 
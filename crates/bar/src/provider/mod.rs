@@ -0,0 +1,183 @@
+//! Data sources shared between components
+//!
+//! Each source runs once, on a background thread started by its first subscriber, and
+//! publishes typed snapshots. When the last [`Subscription`] is dropped the thread notices on
+//! its next publish and stops, so unused sources cost nothing.
+
+pub mod net_dev;
+pub mod system;
+
+pub use net_dev::NetDevSnapshot;
+pub use system::SystemSnapshot;
+
+use crate::wakeup;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Shared<T> {
+    snapshot: Mutex<T>,
+    /// Bumped whenever a changed snapshot is published
+    version: AtomicU64,
+}
+
+/// A data source; declare one as a `static` and call [`Provider::subscribe`]
+pub struct Provider<T> {
+    current: Mutex<Weak<Shared<T>>>,
+    run: fn(Publisher<T>),
+}
+
+impl<T: Clone + Default + PartialEq + Send + 'static> Provider<T> {
+    /// `run` publishes snapshots until [`Publisher::publish`] reports no subscribers are left
+    pub const fn new(run: fn(Publisher<T>)) -> Self {
+        Self {
+            current: Mutex::new(Weak::new()),
+            run,
+        }
+    }
+
+    /// Join the running source, or start it if nobody else is subscribed
+    pub fn subscribe(&self) -> Subscription<T> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(shared) = current.upgrade() {
+            return Subscription { shared };
+        }
+
+        let shared = Arc::new(Shared::default());
+        *current = Arc::downgrade(&shared);
+        let publisher = Publisher {
+            shared: Arc::downgrade(&shared),
+        };
+        let run = self.run;
+        std::thread::spawn(move || run(publisher));
+        Subscription { shared }
+    }
+}
+
+/// Start a source of its own, for a subscriber with settings nobody else shares; it stops once
+/// the returned subscription is dropped
+pub fn spawn<T>(run: impl FnOnce(Publisher<T>) + Send + 'static) -> Subscription<T>
+where
+    T: Clone + Default + PartialEq + Send + 'static,
+{
+    let shared = Arc::new(Shared::default());
+    let publisher = Publisher {
+        shared: Arc::downgrade(&shared),
+    };
+    std::thread::spawn(move || run(publisher));
+    Subscription { shared }
+}
+
+/// A handle on a running source; the source stops once every subscription is dropped
+#[derive(Debug, Clone)]
+pub struct Subscription<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Clone + Default> Subscription<T> {
+    /// The latest snapshot, or the default until the source has published one
+    pub fn snapshot(&self) -> T {
+        self.shared
+            .snapshot
+            .lock()
+            .map(|snapshot| snapshot.clone())
+            .unwrap_or_default()
+    }
+
+    /// Changes whenever the snapshot does, for cheap change detection
+    pub fn version(&self) -> u64 {
+        self.shared.version.load(Ordering::Acquire)
+    }
+}
+
+/// The source's side of a [`Provider`]
+#[derive(Debug)]
+pub struct Publisher<T> {
+    shared: Weak<Shared<T>>,
+}
+
+impl<T: PartialEq> Publisher<T> {
+    /// Replace the snapshot; returns `false` once there are no subscribers left
+    pub fn publish(&self, snapshot: T) -> bool {
+        let Some(shared) = self.shared.upgrade() else {
+            return false;
+        };
+        if let Ok(mut current) = shared.snapshot.lock()
+            && *current != snapshot
+        {
+            *current = snapshot;
+            shared.version.fetch_add(1, Ordering::Release);
+            wakeup::notify();
+        }
+        true
+    }
+
+    pub fn is_subscribed(&self) -> bool {
+        self.shared.strong_count() > 0
+    }
+
+    /// Publish `poll()` every `interval` until there are no subscribers left
+    pub fn poll_every(&self, interval: Duration, mut poll: impl FnMut() -> T) {
+        while self.publish(poll()) {
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Instant;
+
+    static STARTS: AtomicU32 = AtomicU32::new(0);
+    static POLLS: AtomicU32 = AtomicU32::new(0);
+    static COUNTER: Provider<u32> = Provider::new(|publisher| {
+        STARTS.fetch_add(1, Ordering::SeqCst);
+        publisher.poll_every(Duration::from_millis(5), || {
+            POLLS.fetch_add(1, Ordering::SeqCst) + 1
+        });
+    });
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_shared_and_stopped() {
+        let first = COUNTER.subscribe();
+        let second = COUNTER.subscribe();
+        wait_for(|| first.snapshot() > 2);
+        assert_eq!(STARTS.load(Ordering::SeqCst), 1);
+        assert!(second.version() > 0);
+
+        drop(first);
+        drop(second);
+        // The next poll notices nobody is listening
+        std::thread::sleep(Duration::from_millis(50));
+        let stopped_at = POLLS.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(POLLS.load(Ordering::SeqCst), stopped_at);
+
+        let third = COUNTER.subscribe();
+        wait_for(|| third.snapshot() > stopped_at);
+        assert_eq!(STARTS.load(Ordering::SeqCst), 2);
+    }
+    #[test]
+    fn test_spawn() {
+        let (stopped_tx, stopped) = std::sync::mpsc::channel();
+        let subscription = spawn(move |publisher| {
+            publisher.poll_every(Duration::from_millis(5), || 7);
+            let _ = stopped_tx.send(());
+        });
+        wait_for(|| subscription.snapshot() == 7);
+
+        drop(subscription);
+        assert!(stopped.recv_timeout(Duration::from_secs(2)).is_ok());
+    }
+}
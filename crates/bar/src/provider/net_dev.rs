@@ -0,0 +1,92 @@
+use super::{Provider, Publisher, Subscription};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static NET_DEV: Provider<NetDevSnapshot> = Provider::new(run);
+
+/// Byte counters of one interface since boot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Every interface in `/proc/net/dev`, read at `read_at`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetDevSnapshot {
+    pub interfaces: Vec<InterfaceCounters>,
+    pub read_at: Option<Instant>,
+}
+
+impl NetDevSnapshot {
+    pub fn interface(&self, name: &str) -> Option<&InterfaceCounters> {
+        self.interfaces.iter().find(|i| i.name == name)
+    }
+}
+
+pub fn subscribe() -> Subscription<NetDevSnapshot> {
+    NET_DEV.subscribe()
+}
+
+/// Read `proc_root/net/dev`; `None` if it's missing, e.g. outside Linux
+pub fn read(proc_root: &Path) -> Option<NetDevSnapshot> {
+    let content = std::fs::read_to_string(proc_root.join("net/dev")).ok()?;
+    Some(NetDevSnapshot {
+        interfaces: parse(&content),
+        read_at: Some(Instant::now()),
+    })
+}
+
+/// Parse the table after its two header lines
+pub fn parse(content: &str) -> Vec<InterfaceCounters> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|field| field.parse().ok())
+                .collect::<Option<_>>()?;
+            Some(InterfaceCounters {
+                name: name.trim().to_string(),
+                rx_bytes: *counters.first()?,
+                tx_bytes: *counters.get(8)?,
+            })
+        })
+        .collect()
+}
+
+fn run(publisher: Publisher<NetDevSnapshot>) {
+    publisher.poll_every(POLL_INTERVAL, || {
+        read(Path::new("/proc")).unwrap_or_default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:   12345      10    0    0    0     0          0         0    12345      10    0    0    0     0       0          0
+wlp3s0: 987654321  654321    0    0    0     0          0         0 12345678   54321    0    0    0     0       0          0
+";
+        let interfaces = parse(content);
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!(
+            interfaces[1],
+            InterfaceCounters {
+                name: "wlp3s0".to_string(),
+                rx_bytes: 987654321,
+                tx_bytes: 12345678,
+            }
+        );
+    }
+}
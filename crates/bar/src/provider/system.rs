@@ -0,0 +1,64 @@
+use super::{Provider, Publisher, Subscription};
+use std::time::Duration;
+use sysinfo::{Components, CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+/// CPU usage is averaged over this, so it's also the finest sparkline resolution
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static SYSTEM: Provider<SystemSnapshot> = Provider::new(run);
+
+/// CPU, memory and temperature readings from `sysinfo`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SystemSnapshot {
+    /// Average over all cores, in percent
+    pub cpu_usage: f32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// First sensor that looks like the CPU package or a core, in °C
+    pub cpu_temperature: Option<f32>,
+}
+
+impl SystemSnapshot {
+    pub fn memory_percent(&self) -> u32 {
+        if self.memory_total == 0 {
+            return 0;
+        }
+        (self.memory_used as f64 / self.memory_total as f64 * 100.0) as u32
+    }
+}
+
+pub fn subscribe() -> Subscription<SystemSnapshot> {
+    SYSTEM.subscribe()
+}
+
+fn run(publisher: Publisher<SystemSnapshot>) {
+    let mut system = System::new_with_specifics(
+        RefreshKind::nothing()
+            .with_cpu(CpuRefreshKind::everything())
+            .with_memory(MemoryRefreshKind::everything()),
+    );
+    let mut components = Components::new_with_refreshed_list();
+
+    publisher.poll_every(POLL_INTERVAL, || {
+        system.refresh_cpu_all();
+        system.refresh_memory();
+        components.refresh(true);
+
+        let cpus = system.cpus();
+        let cpu_usage = cpus.iter().map(|cpu| cpu.cpu_usage()).sum::<f32>() / cpus.len() as f32;
+        let cpu_temperature = components
+            .iter()
+            .find(|c| {
+                let label = c.label().to_lowercase();
+                label.contains("cpu") || label.contains("core") || label.contains("package")
+            })
+            .and_then(|c| c.temperature());
+
+        SystemSnapshot {
+            cpu_usage,
+            memory_used: system.used_memory(),
+            memory_total: system.total_memory(),
+            cpu_temperature,
+        }
+    });
+}
@@ -0,0 +1,985 @@
+//! A client for PulseAudio's native protocol, which pipewire-pulse also serves
+
+mod tagstruct;
+
+pub use tagstruct::TagStruct;
+
+use color_eyre::eyre::{Result, eyre};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::logging;
+use crate::provider::{Provider, Publisher, Subscription};
+
+/// Protocol version we speak; the server may negotiate it down
+const PROTOCOL_VERSION: u32 = 32;
+/// Oldest version whose info layouts we parse (PulseAudio 2.0)
+const MIN_PROTOCOL_VERSION: u32 = 24;
+/// The reply to AUTH carries shared memory flags in the upper bits
+const VERSION_MASK: u32 = 0xFFFF;
+
+const COMMAND_ERROR: u32 = 0;
+const COMMAND_REPLY: u32 = 2;
+const COMMAND_AUTH: u32 = 8;
+const COMMAND_SET_CLIENT_NAME: u32 = 9;
+const COMMAND_GET_SINK_INFO: u32 = 21;
+const COMMAND_GET_SOURCE_INFO: u32 = 23;
+const COMMAND_GET_SOURCE_INFO_LIST: u32 = 24;
+const COMMAND_GET_SOURCE_OUTPUT_INFO_LIST: u32 = 32;
+const COMMAND_SUBSCRIBE: u32 = 35;
+const COMMAND_SET_SOURCE_MUTE: u32 = 40;
+const COMMAND_SUBSCRIBE_EVENT: u32 = 66;
+
+/// Channel of command packets; other channels carry audio
+const CONTROL_CHANNEL: u32 = u32::MAX;
+const INVALID_INDEX: u32 = u32::MAX;
+const HEADER_LENGTH: usize = 20;
+const MAX_PACKET_LENGTH: usize = 16 * 1024 * 1024;
+const COOKIE_LENGTH: usize = 256;
+/// 100% volume
+const VOLUME_NORM: u64 = 0x10000;
+
+pub const SUBSCRIPTION_SINK: u32 = 0x0001;
+pub const SUBSCRIPTION_SOURCE: u32 = 0x0002;
+pub const SUBSCRIPTION_SOURCE_OUTPUT: u32 = 0x0008;
+pub const SUBSCRIPTION_SERVER: u32 = 0x0080;
+
+pub const FACILITY_SINK: u32 = 0x00;
+const FACILITY_MASK: u32 = 0x0F;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+static AUDIO: Provider<AudioState> = Provider::new(run);
+static REQUESTS: LazyLock<Requests> = LazyLock::new(Requests::new);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DeviceKind {
+    #[default]
+    Speakers,
+    Headphones,
+    Bluetooth,
+}
+
+impl DeviceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceKind::Speakers => "speakers",
+            DeviceKind::Headphones => "headphones",
+            DeviceKind::Bluetooth => "bluetooth",
+        }
+    }
+
+    fn classify(name: &str, properties: &HashMap<String, String>, port: Option<&str>) -> Self {
+        let property = |key: &str| properties.get(key).map(String::as_str);
+        if property("device.bus") == Some("bluetooth")
+            || property("device.api") == Some("bluez5")
+            || name.starts_with("bluez")
+        {
+            return DeviceKind::Bluetooth;
+        }
+
+        let headphones = matches!(
+            property("device.form_factor"),
+            Some("headphone" | "headset" | "hands-free")
+        ) || port.is_some_and(|port| {
+            let port = port.to_lowercase();
+            port.contains("headphone") || port.contains("headset")
+        });
+        if headphones {
+            DeviceKind::Headphones
+        } else {
+            DeviceKind::Speakers
+        }
+    }
+}
+
+/// A sink or source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub index: u32,
+    pub name: String,
+    /// Human readable name, e.g. `Built-in Audio Analog Stereo`
+    pub description: String,
+    /// Average over channels, in percent
+    pub volume: u32,
+    pub muted: bool,
+    pub kind: DeviceKind,
+}
+
+impl Device {
+    /// Sink and source info share one layout, apart from the meaning of fields we skip
+    fn parse(reply: &mut TagStruct) -> Result<Self> {
+        let index = reply.get_u32()?;
+        let name = reply.get_string()?.unwrap_or_default();
+        let description = reply.get_string()?.unwrap_or_else(|| name.clone());
+        reply.get_sample_spec()?;
+        reply.get_channel_map()?;
+        reply.get_u32()?; // owner module
+        let volumes = reply.get_cvolume()?;
+        let muted = reply.get_bool()?;
+        reply.get_u32()?; // monitor source, or monitored sink
+        reply.get_string()?;
+        reply.get_usec()?; // latency
+        reply.get_string()?; // driver
+        reply.get_u32()?; // flags
+        let properties = reply.get_proplist()?;
+        reply.get_usec()?; // configured latency
+        reply.get_volume()?; // base volume
+        reply.get_u32()?; // state
+        reply.get_u32()?; // volume steps
+        reply.get_u32()?; // card
+        for _ in 0..reply.get_u32()? {
+            reply.get_string()?; // name
+            reply.get_string()?; // description
+            reply.get_u32()?; // priority
+            reply.get_u32()?; // availability
+        }
+        let active_port = reply.get_string()?;
+        for _ in 0..reply.get_u8()? {
+            reply.get_format_info()?;
+        }
+
+        Ok(Self {
+            index,
+            kind: DeviceKind::classify(&name, &properties, active_port.as_deref()),
+            name,
+            description,
+            volume: average_percent(&volumes),
+            muted,
+        })
+    }
+}
+
+fn average_percent(volumes: &[u32]) -> u32 {
+    if volumes.is_empty() {
+        return 0;
+    }
+    let average = volumes.iter().map(|&v| v as u64).sum::<u64>() / volumes.len() as u64;
+    ((average * 100 + VOLUME_NORM / 2) / VOLUME_NORM) as u32
+}
+
+/// A stream recording from a source
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceOutput {
+    pub index: u32,
+    pub source: u32,
+    /// Paused streams don't count as recording
+    pub corked: bool,
+}
+
+impl SourceOutput {
+    fn parse(reply: &mut TagStruct) -> Result<Self> {
+        let index = reply.get_u32()?;
+        reply.get_string()?; // name
+        reply.get_u32()?; // owner module
+        reply.get_u32()?; // client
+        let source = reply.get_u32()?;
+        reply.get_sample_spec()?;
+        reply.get_channel_map()?;
+        reply.get_usec()?; // buffer latency
+        reply.get_usec()?; // source latency
+        reply.get_string()?; // resample method
+        reply.get_string()?; // driver
+        reply.get_proplist()?;
+        let corked = reply.get_bool()?;
+        reply.get_cvolume()?;
+        reply.get_bool()?; // muted
+        reply.get_bool()?; // has volume
+        reply.get_bool()?; // volume writable
+        reply.get_format_info()?;
+        Ok(Self {
+            index,
+            source,
+            corked,
+        })
+    }
+}
+
+/// A change notification for a subscribed facility
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub facility: u32,
+    pub index: u32,
+}
+
+/// A connection to the sound server's native protocol socket
+#[derive(Debug)]
+pub struct PulseConnection {
+    stream: UnixStream,
+    version: u32,
+    next_tag: u32,
+    /// Events that arrived while waiting for a reply
+    events: VecDeque<Event>,
+}
+
+impl PulseConnection {
+    pub fn connect(path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        let mut connection = Self {
+            stream,
+            version: PROTOCOL_VERSION,
+            next_tag: 0,
+            events: VecDeque::new(),
+        };
+
+        let cookie = read_cookie();
+        let mut reply = connection.request(COMMAND_AUTH, |args| {
+            args.put_u32(PROTOCOL_VERSION).put_arbitrary(&cookie);
+        })?;
+        connection.version = PROTOCOL_VERSION.min(reply.get_u32()? & VERSION_MASK);
+        if connection.version < MIN_PROTOCOL_VERSION {
+            return Err(eyre!(
+                "Sound server speaks protocol version {}, {} is required",
+                connection.version,
+                MIN_PROTOCOL_VERSION
+            ));
+        }
+
+        connection.request(COMMAND_SET_CLIENT_NAME, |args| {
+            args.put_proplist(&[("application.name", "catfood-bar")]);
+        })?;
+        Ok(connection)
+    }
+
+    /// Start receiving events for the `SUBSCRIPTION_*` facilities in `mask`
+    pub fn subscribe(&mut self, mask: u32) -> Result<()> {
+        self.request(COMMAND_SUBSCRIBE, |args| {
+            args.put_u32(mask);
+        })?;
+        Ok(())
+    }
+
+    /// The default sink, or `None` if the server has no sinks
+    pub fn default_sink(&mut self) -> Result<Option<Device>> {
+        self.device(COMMAND_GET_SINK_INFO, "@DEFAULT_SINK@")
+    }
+
+    /// The default source, or `None` if the server has no sources
+    pub fn default_source(&mut self) -> Result<Option<Device>> {
+        self.device(COMMAND_GET_SOURCE_INFO, "@DEFAULT_SOURCE@")
+    }
+
+    pub fn sources(&mut self) -> Result<Vec<Device>> {
+        let mut reply = self.request(COMMAND_GET_SOURCE_INFO_LIST, |_| {})?;
+        let mut sources = Vec::new();
+        while !reply.is_empty() {
+            sources.push(Device::parse(&mut reply)?);
+        }
+        Ok(sources)
+    }
+
+    pub fn source_outputs(&mut self) -> Result<Vec<SourceOutput>> {
+        let mut reply = self.request(COMMAND_GET_SOURCE_OUTPUT_INFO_LIST, |_| {})?;
+        let mut outputs = Vec::new();
+        while !reply.is_empty() {
+            outputs.push(SourceOutput::parse(&mut reply)?);
+        }
+        Ok(outputs)
+    }
+
+    pub fn set_default_source_mute(&mut self, muted: bool) -> Result<()> {
+        self.request(COMMAND_SET_SOURCE_MUTE, |args| {
+            args.put_u32(INVALID_INDEX)
+                .put_string(Some("@DEFAULT_SOURCE@"))
+                .put_bool(muted);
+        })?;
+        Ok(())
+    }
+
+    /// Look a device up by name, treating errors like "no such entity" as absent
+    fn device(&mut self, command: u32, name: &str) -> Result<Option<Device>> {
+        let reply = self.exchange(command, |args| {
+            args.put_u32(INVALID_INDEX).put_string(Some(name));
+        })?;
+        match reply {
+            Ok(mut reply) => Ok(Some(Device::parse(&mut reply)?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Block until the server reports a change
+    pub fn next_event(&mut self) -> Result<Event> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+
+        self.stream.set_read_timeout(None)?;
+        let event = loop {
+            let (command, _, mut packet) = self.read_command()?;
+            if command == COMMAND_SUBSCRIBE_EVENT {
+                break parse_event(&mut packet)?;
+            }
+        };
+        self.stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        Ok(event)
+    }
+
+    /// Drop events that have already arrived; a burst of them, e.g. while dragging a volume
+    /// slider, then costs one round of queries instead of one per event
+    pub fn discard_pending_events(&mut self) -> Result<()> {
+        self.events.clear();
+        while self.has_pending_data()? {
+            self.read_command()?;
+        }
+        Ok(())
+    }
+
+    /// Block until the server sends something or `wake` becomes readable
+    fn wait(&mut self, wake: Option<&UnixStream>) -> Result<()> {
+        if !self.events.is_empty() {
+            return Ok(());
+        }
+
+        let mut pollfds: Vec<libc::pollfd> = std::iter::once(&self.stream)
+            .chain(wake)
+            .map(|stream| libc::pollfd {
+                fd: stream.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // Retried when a signal interrupts the wait
+        while unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, -1) } == -1 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(e.into());
+            }
+        }
+
+        let server = pollfds[0].revents;
+        if server & (libc::POLLHUP | libc::POLLERR) != 0 && server & libc::POLLIN == 0 {
+            return Err(eyre!("PulseAudio connection closed"));
+        }
+        Ok(())
+    }
+
+    fn has_pending_data(&self) -> Result<bool> {
+        let mut pollfd = libc::pollfd {
+            fd: self.stream.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, 0) } {
+            -1 => Err(std::io::Error::last_os_error().into()),
+            ready => Ok(ready > 0),
+        }
+    }
+
+    fn request(&mut self, command: u32, args: impl FnOnce(&mut TagStruct)) -> Result<TagStruct> {
+        self.exchange(command, args)?
+            .map_err(|code| eyre!("PulseAudio command {} failed with error {}", command, code))
+    }
+
+    /// Send a command and wait for its reply, or the server's error code
+    fn exchange(
+        &mut self,
+        command: u32,
+        args: impl FnOnce(&mut TagStruct),
+    ) -> Result<std::result::Result<TagStruct, u32>> {
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+
+        let mut packet = TagStruct::new();
+        packet.put_u32(command).put_u32(tag);
+        args(&mut packet);
+        self.write_packet(&packet.into_bytes())?;
+
+        loop {
+            let (reply_command, reply_tag, mut packet) = self.read_command()?;
+            match reply_command {
+                COMMAND_SUBSCRIBE_EVENT => self.events.push_back(parse_event(&mut packet)?),
+                COMMAND_REPLY if reply_tag == tag => return Ok(Ok(packet)),
+                COMMAND_ERROR if reply_tag == tag => return Ok(Err(packet.get_u32()?)),
+                _ => {}
+            }
+        }
+    }
+
+    fn write_packet(&mut self, payload: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(HEADER_LENGTH + payload.len());
+        for field in [payload.len() as u32, CONTROL_CHANNEL, 0, 0, 0] {
+            packet.extend_from_slice(&field.to_be_bytes());
+        }
+        packet.extend_from_slice(payload);
+        self.stream.write_all(&packet)?;
+        Ok(())
+    }
+
+    /// The next command packet as (command, tag, remaining fields)
+    fn read_command(&mut self) -> Result<(u32, u32, TagStruct)> {
+        loop {
+            let mut header = [0u8; HEADER_LENGTH];
+            self.stream.read_exact(&mut header)?;
+            let length = u32::from_be_bytes(header[0..4].try_into()?) as usize;
+            let channel = u32::from_be_bytes(header[4..8].try_into()?);
+            if length > MAX_PACKET_LENGTH {
+                return Err(eyre!("PulseAudio packet of {} bytes is too large", length));
+            }
+
+            let mut payload = vec![0u8; length];
+            self.stream.read_exact(&mut payload)?;
+            if channel != CONTROL_CHANNEL {
+                continue;
+            }
+
+            let mut packet = TagStruct::from_bytes(payload);
+            let command = packet.get_u32()?;
+            let tag = packet.get_u32()?;
+            return Ok((command, tag, packet));
+        }
+    }
+}
+
+fn parse_event(packet: &mut TagStruct) -> Result<Event> {
+    let event_type = packet.get_u32()?;
+    Ok(Event {
+        facility: event_type & FACILITY_MASK,
+        index: packet.get_u32()?,
+    })
+}
+
+/// The server socket from `$PULSE_SERVER`, falling back to the per-user runtime directory
+pub fn socket_path() -> Option<PathBuf> {
+    if let Ok(servers) = std::env::var("PULSE_SERVER") {
+        // Entries look like `unix:/path`, `/path` or `{machine-id}unix:/path`; TCP isn't supported
+        let local = servers.split_whitespace().find_map(|server| {
+            let server = server.split_once('}').map_or(server, |(_, rest)| rest);
+            let path = server.strip_prefix("unix:").unwrap_or(server);
+            path.starts_with('/').then(|| PathBuf::from(path))
+        });
+        if local.is_some() {
+            return local;
+        }
+    }
+
+    if let Ok(runtime) = std::env::var("PULSE_RUNTIME_PATH") {
+        return Some(PathBuf::from(runtime).join("native"));
+    }
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(format!("/run/user/{}", unsafe { libc::getuid() })));
+    Some(runtime_dir.join("pulse").join("native"))
+}
+
+/// The authentication cookie, or zeros for servers that don't check it (pipewire-pulse)
+fn read_cookie() -> Vec<u8> {
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".config"));
+    let candidates = [
+        std::env::var("PULSE_COOKIE").ok().map(PathBuf::from),
+        Some(config_dir.join("pulse").join("cookie")),
+        Some(home.join(".pulse-cookie")),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find(|cookie| cookie.len() >= COOKIE_LENGTH)
+        .map(|cookie| cookie[..COOKIE_LENGTH].to_vec())
+        .unwrap_or_else(|| vec![0; COOKIE_LENGTH])
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioState {
+    pub sink: Option<Device>,
+    pub source: Option<Device>,
+    /// Some application is recording from a microphone (monitors of outputs don't count)
+    pub capturing: bool,
+}
+
+/// Follow the default devices; the connection is closed after the last subscription is
+/// dropped and the next event arrives
+pub fn subscribe() -> Subscription<AudioState> {
+    AUDIO.subscribe()
+}
+
+fn run(publisher: Publisher<AudioState>) {
+    // Only log each distinct failure once, e.g. while no server is running
+    let mut last_error = None;
+    while publisher.is_subscribed() {
+        let result = socket_path()
+            .ok_or_else(|| eyre!("No PulseAudio socket"))
+            .and_then(|path| follow(&path, &publisher, &mut last_error));
+        if let Err(e) = result {
+            let message = format!("{}", e);
+            if last_error.as_ref() != Some(&message) {
+                logging::log_component_error("PULSE", &message);
+                last_error = Some(message);
+            }
+        }
+
+        // Clicks made while disconnected are stale by the time the server is back
+        REQUESTS.take();
+        if !publisher.publish(AudioState::default()) {
+            return;
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Changes asked for by components, sent over the subscription's connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Request {
+    SetMicrophoneMuted(bool),
+}
+
+/// Requests waiting for the connection's thread, which is woken up to send them
+#[derive(Debug)]
+struct Requests {
+    pending: Mutex<Vec<Request>>,
+    /// Written to after queueing a request and read by the connection's thread
+    wake: Option<(UnixStream, UnixStream)>,
+}
+
+impl Requests {
+    fn new() -> Self {
+        let wake = UnixStream::pair().and_then(|(writer, reader)| {
+            writer.set_nonblocking(true)?;
+            reader.set_nonblocking(true)?;
+            Ok((writer, reader))
+        });
+        if let Err(e) = &wake {
+            logging::log_component_error("PULSE", &format!("Failed to create wakeup: {}", e));
+        }
+        Self {
+            pending: Mutex::new(Vec::new()),
+            wake: wake.ok(),
+        }
+    }
+
+    fn push(&self, request: Request) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.push(request);
+        }
+        // A full buffer means a wakeup is pending already
+        if let Some((writer, _)) = &self.wake {
+            let _ = (&*writer).write(&[0]);
+        }
+    }
+
+    fn take(&self) -> Vec<Request> {
+        if let Some((_, reader)) = &self.wake {
+            let mut buf = [0u8; 64];
+            while matches!((&*reader).read(&mut buf), Ok(n) if n > 0) {}
+        }
+        self.pending
+            .lock()
+            .map(|mut pending| std::mem::take(&mut *pending))
+            .unwrap_or_default()
+    }
+
+    fn wake(&self) -> Option<&UnixStream> {
+        self.wake.as_ref().map(|(_, reader)| reader)
+    }
+}
+
+/// Mute or unmute the default source; sent by the subscription's connection, so a slow or
+/// absent sound server never holds up the caller
+pub fn set_microphone_muted(muted: bool) {
+    REQUESTS.push(Request::SetMicrophoneMuted(muted));
+}
+
+/// Publish the state on every device, stream or server change until the connection fails or
+/// nobody is subscribed
+fn follow(
+    path: &Path,
+    publisher: &Publisher<AudioState>,
+    last_error: &mut Option<String>,
+) -> Result<()> {
+    let mut connection = PulseConnection::connect(path)?;
+    connection.subscribe(
+        SUBSCRIPTION_SINK | SUBSCRIPTION_SOURCE | SUBSCRIPTION_SOURCE_OUTPUT | SUBSCRIPTION_SERVER,
+    )?;
+    *last_error = None;
+
+    loop {
+        for request in REQUESTS.take() {
+            let result = match request {
+                Request::SetMicrophoneMuted(muted) => connection.set_default_source_mute(muted),
+            };
+            if let Err(e) = result {
+                logging::log_component_error("PULSE", &format!("{}", e));
+            }
+        }
+
+        let new_state = AudioState {
+            sink: connection.default_sink()?,
+            source: connection.default_source()?,
+            capturing: is_capturing(&mut connection)?,
+        };
+        if !publisher.publish(new_state) {
+            return Ok(());
+        }
+        connection.wait(REQUESTS.wake())?;
+        connection.discard_pending_events()?;
+    }
+}
+
+fn is_capturing(connection: &mut PulseConnection) -> Result<bool> {
+    let outputs = connection.source_outputs()?;
+    if outputs.iter().all(|output| output.corked) {
+        return Ok(false);
+    }
+
+    let monitors: Vec<u32> = connection
+        .sources()?
+        .into_iter()
+        .filter(|source| source.name.ends_with(".monitor"))
+        .map(|source| source.index)
+        .collect();
+    Ok(outputs
+        .iter()
+        .any(|output| !output.corked && !monitors.contains(&output.source)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::process::Command;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct FakeAudio {
+        sink_volume: u32,
+        source_muted: bool,
+        /// Whether the microphone stream is uncorked; a monitor stream always is
+        recording: bool,
+    }
+
+    struct FakeServer {
+        audio: Arc<Mutex<FakeAudio>>,
+        client: Arc<Mutex<Option<UnixStream>>>,
+    }
+
+    impl FakeServer {
+        fn push_event(&self, event_type: u32, index: u32) {
+            let mut packet = TagStruct::new();
+            packet
+                .put_u32(COMMAND_SUBSCRIBE_EVENT)
+                .put_u32(u32::MAX)
+                .put_u32(event_type)
+                .put_u32(index);
+            let mut client = self.client.lock().unwrap();
+            write_packet(client.as_mut().unwrap(), packet);
+        }
+    }
+
+    fn write_packet(stream: &mut UnixStream, packet: TagStruct) {
+        let payload = packet.into_bytes();
+        let mut bytes = Vec::new();
+        for field in [payload.len() as u32, CONTROL_CHANNEL, 0, 0, 0] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.extend_from_slice(&payload);
+        stream.write_all(&bytes).unwrap();
+    }
+
+    /// Sink or source info in the protocol version 32 layout
+    fn put_device(
+        reply: &mut TagStruct,
+        index: u32,
+        name: &str,
+        description: &str,
+        volume: u32,
+        muted: bool,
+        properties: &[(&str, &str)],
+    ) {
+        reply
+            .put_u32(index)
+            .put_string(Some(name))
+            .put_string(Some(description))
+            .put_sample_spec(3, 2, 48000)
+            .put_channel_map(&[1, 2])
+            .put_u32(INVALID_INDEX)
+            .put_cvolume(&[volume, volume])
+            .put_bool(muted)
+            .put_u32(INVALID_INDEX)
+            .put_string(None)
+            .put_usec(0)
+            .put_string(Some("module-alsa-card.c"))
+            .put_u32(0)
+            .put_proplist(properties)
+            .put_usec(0)
+            .put_volume(VOLUME_NORM as u32)
+            .put_u32(0)
+            .put_u32(65537)
+            .put_u32(1)
+            .put_u32(1)
+            .put_string(Some("headphone-output"))
+            .put_string(Some("Headphone"))
+            .put_u32(0)
+            .put_u32(0)
+            .put_string(Some("headphone-output"))
+            .put_u8(1)
+            .put_format_info(1, &[]);
+    }
+
+    fn put_source_output(reply: &mut TagStruct, index: u32, source: u32, corked: bool) {
+        reply
+            .put_u32(index)
+            .put_string(Some("Recording"))
+            .put_u32(INVALID_INDEX)
+            .put_u32(9)
+            .put_u32(source)
+            .put_sample_spec(3, 1, 48000)
+            .put_channel_map(&[0])
+            .put_usec(0)
+            .put_usec(0)
+            .put_string(None)
+            .put_string(Some("protocol-native.c"))
+            .put_proplist(&[("application.name", "Firefox")])
+            .put_bool(corked)
+            .put_cvolume(&[VOLUME_NORM as u32])
+            .put_bool(false)
+            .put_bool(true)
+            .put_bool(true)
+            .put_format_info(1, &[]);
+    }
+
+    fn put_microphone(reply: &mut TagStruct, audio: &FakeAudio) {
+        put_device(
+            reply,
+            1,
+            "alsa_input.pci-0000_00_1f.3.analog-stereo",
+            "Built-in Audio Analog Stereo",
+            VOLUME_NORM as u32,
+            audio.source_muted,
+            &[],
+        );
+    }
+
+    fn spawn_fake_server(path: &Path) -> FakeServer {
+        let listener = UnixListener::bind(path).unwrap();
+        let audio = Arc::new(Mutex::new(FakeAudio {
+            sink_volume: VOLUME_NORM as u32 / 2,
+            ..FakeAudio::default()
+        }));
+        let client = Arc::new(Mutex::new(None));
+        let server = FakeServer {
+            audio: audio.clone(),
+            client: client.clone(),
+        };
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            *client.lock().unwrap() = Some(stream.try_clone().unwrap());
+            loop {
+                let mut header = [0u8; HEADER_LENGTH];
+                if stream.read_exact(&mut header).is_err() {
+                    return;
+                }
+                let length = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+                let mut payload = vec![0u8; length];
+                stream.read_exact(&mut payload).unwrap();
+
+                let mut request = TagStruct::from_bytes(payload);
+                let command = request.get_u32().unwrap();
+                let tag = request.get_u32().unwrap();
+                let mut audio = audio.lock().unwrap();
+                let mut reply = TagStruct::new();
+                reply.put_u32(COMMAND_REPLY).put_u32(tag);
+                match command {
+                    COMMAND_AUTH => {
+                        reply.put_u32(35 | 0x8000_0000);
+                    }
+                    COMMAND_SET_CLIENT_NAME => {
+                        reply.put_u32(7);
+                    }
+                    COMMAND_SUBSCRIBE => {}
+                    COMMAND_GET_SINK_INFO => put_device(
+                        &mut reply,
+                        3,
+                        "bluez_output.00_1B_66.1",
+                        "WH-1000XM4",
+                        audio.sink_volume,
+                        false,
+                        &[("device.bus", "bluetooth")],
+                    ),
+                    COMMAND_GET_SOURCE_INFO => put_microphone(&mut reply, &audio),
+                    COMMAND_GET_SOURCE_INFO_LIST => {
+                        put_microphone(&mut reply, &audio);
+                        put_device(
+                            &mut reply,
+                            4,
+                            "bluez_output.00_1B_66.1.monitor",
+                            "Monitor of WH-1000XM4",
+                            VOLUME_NORM as u32,
+                            false,
+                            &[],
+                        );
+                    }
+                    COMMAND_GET_SOURCE_OUTPUT_INFO_LIST => {
+                        put_source_output(&mut reply, 10, 4, false);
+                        put_source_output(&mut reply, 11, 1, !audio.recording);
+                    }
+                    COMMAND_SET_SOURCE_MUTE => {
+                        request.get_u32().unwrap();
+                        request.get_string().unwrap();
+                        audio.source_muted = request.get_bool().unwrap();
+                    }
+                    _ => {
+                        reply = TagStruct::new();
+                        reply.put_u32(COMMAND_ERROR).put_u32(tag).put_u32(2);
+                    }
+                }
+                write_packet(client.lock().unwrap().as_mut().unwrap(), reply);
+            }
+        });
+        server
+    }
+
+    #[test]
+    fn test_default_sink_and_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn_fake_server(&path);
+
+        let mut connection = PulseConnection::connect(&path).unwrap();
+        assert_eq!(connection.version, PROTOCOL_VERSION);
+        connection
+            .subscribe(SUBSCRIPTION_SINK | SUBSCRIPTION_SERVER)
+            .unwrap();
+
+        let sink = connection.default_sink().unwrap().unwrap();
+        assert_eq!(sink.description, "WH-1000XM4");
+        assert_eq!(sink.volume, 50);
+        assert!(!sink.muted);
+        assert_eq!(sink.kind, DeviceKind::Bluetooth);
+
+        server.audio.lock().unwrap().sink_volume = VOLUME_NORM as u32 * 3 / 4;
+        server.push_event(0x10 | FACILITY_SINK, 3);
+        assert_eq!(
+            connection.next_event().unwrap(),
+            Event {
+                facility: FACILITY_SINK,
+                index: 3
+            }
+        );
+        assert_eq!(connection.default_sink().unwrap().unwrap().volume, 75);
+
+        for _ in 0..3 {
+            server.push_event(0x10 | FACILITY_SINK, 3);
+        }
+        connection.next_event().unwrap();
+        connection.discard_pending_events().unwrap();
+        connection.default_sink().unwrap();
+        assert!(connection.events.is_empty());
+    }
+
+    #[test]
+    fn test_microphone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("native");
+        let server = spawn_fake_server(&path);
+        let mut connection = PulseConnection::connect(&path).unwrap();
+
+        let source = connection.default_source().unwrap().unwrap();
+        assert_eq!(source.description, "Built-in Audio Analog Stereo");
+        assert!(!source.muted);
+
+        // Only the monitor is being recorded, and the microphone stream is paused
+        assert!(!is_capturing(&mut connection).unwrap());
+        server.audio.lock().unwrap().recording = true;
+        assert!(is_capturing(&mut connection).unwrap());
+
+        connection.set_default_source_mute(true).unwrap();
+        assert!(connection.default_source().unwrap().unwrap().muted);
+    }
+
+    #[test]
+    fn test_requests_use_the_subscription() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("native");
+        let _server = spawn_fake_server(&path);
+        let audio = crate::provider::spawn(move |publisher| {
+            let _ = follow(&path, &publisher, &mut None);
+        });
+
+        let wait_for = |condition: &dyn Fn(&AudioState) -> bool| {
+            let deadline = std::time::Instant::now() + Duration::from_secs(2);
+            while !condition(&audio.snapshot()) {
+                assert!(std::time::Instant::now() < deadline, "timed out");
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        };
+        wait_for(&|state| state.source.as_ref().is_some_and(|s| !s.muted));
+
+        // Queued without touching the server, then sent by the waiting connection
+        set_microphone_muted(true);
+        wait_for(&|state| state.source.as_ref().is_some_and(|s| s.muted));
+    }
+
+    #[test]
+    fn test_classify() {
+        let properties = HashMap::new();
+        assert_eq!(
+            DeviceKind::classify(
+                "alsa_output.pci",
+                &properties,
+                Some("analog-output-headphones")
+            ),
+            DeviceKind::Headphones
+        );
+        assert_eq!(
+            DeviceKind::classify(
+                "alsa_output.pci",
+                &properties,
+                Some("analog-output-speaker")
+            ),
+            DeviceKind::Speakers
+        );
+
+        let properties = HashMap::from([("device.api".to_string(), "bluez5".to_string())]);
+        assert_eq!(
+            DeviceKind::classify("bt", &properties, None),
+            DeviceKind::Bluetooth
+        );
+    }
+
+    /// Run with `cargo test -- --ignored` on a machine running PulseAudio or pipewire-pulse
+    #[test]
+    #[ignore = "needs a running PulseAudio server and pactl"]
+    fn test_null_sink() {
+        let pactl = |args: &[&str]| {
+            let output = Command::new("pactl").args(args).output().unwrap();
+            assert!(output.status.success(), "pactl {:?} failed", args);
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        let previous_default = pactl(&["get-default-sink"]);
+        let module = pactl(&[
+            "load-module",
+            "module-null-sink",
+            "sink_name=catfood_null",
+            "sink_properties=device.description=CatfoodNull",
+        ]);
+        pactl(&["set-default-sink", "catfood_null"]);
+        pactl(&["set-sink-volume", "catfood_null", "42%"]);
+
+        let mut connection = PulseConnection::connect(&socket_path().unwrap()).unwrap();
+        connection.subscribe(SUBSCRIPTION_SINK).unwrap();
+        let sink = connection.default_sink().unwrap().unwrap();
+        assert_eq!(sink.name, "catfood_null");
+        assert_eq!(sink.description, "CatfoodNull");
+        assert_eq!(sink.volume, 42);
+
+        pactl(&["set-sink-mute", "catfood_null", "1"]);
+        assert_eq!(connection.next_event().unwrap().facility, FACILITY_SINK);
+        assert!(connection.default_sink().unwrap().unwrap().muted);
+
+        pactl(&["set-default-sink", &previous_default]);
+        pactl(&["unload-module", &module]);
+    }
+}
@@ -0,0 +1,307 @@
+use color_eyre::eyre::{Result, eyre};
+use std::collections::HashMap;
+
+const TAG_STRING: u8 = b't';
+const TAG_STRING_NULL: u8 = b'N';
+const TAG_U32: u8 = b'L';
+const TAG_U8: u8 = b'B';
+const TAG_SAMPLE_SPEC: u8 = b'a';
+const TAG_ARBITRARY: u8 = b'x';
+const TAG_BOOLEAN_TRUE: u8 = b'1';
+const TAG_BOOLEAN_FALSE: u8 = b'0';
+const TAG_USEC: u8 = b'U';
+const TAG_CHANNEL_MAP: u8 = b'm';
+const TAG_CVOLUME: u8 = b'v';
+const TAG_PROPLIST: u8 = b'P';
+const TAG_VOLUME: u8 = b'V';
+const TAG_FORMAT_INFO: u8 = b'f';
+
+/// The PulseAudio wire format: a sequence of values, each prefixed by a type tag
+#[derive(Debug, Default)]
+pub struct TagStruct {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl TagStruct {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Whether every value has been read
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    pub fn put_u32(&mut self, value: u32) -> &mut Self {
+        self.data.push(TAG_U32);
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn put_u8(&mut self, value: u8) -> &mut Self {
+        self.data.extend_from_slice(&[TAG_U8, value]);
+        self
+    }
+
+    pub fn put_usec(&mut self, value: u64) -> &mut Self {
+        self.data.push(TAG_USEC);
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn put_bool(&mut self, value: bool) -> &mut Self {
+        self.data.push(if value {
+            TAG_BOOLEAN_TRUE
+        } else {
+            TAG_BOOLEAN_FALSE
+        });
+        self
+    }
+
+    pub fn put_string(&mut self, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(value) => {
+                self.data.push(TAG_STRING);
+                self.data.extend_from_slice(value.as_bytes());
+                self.data.push(0);
+            }
+            None => self.data.push(TAG_STRING_NULL),
+        }
+        self
+    }
+
+    pub fn put_arbitrary(&mut self, value: &[u8]) -> &mut Self {
+        self.data.push(TAG_ARBITRARY);
+        self.data
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Sample format, channel count and rate
+    pub fn put_sample_spec(&mut self, format: u8, channels: u8, rate: u32) -> &mut Self {
+        self.data
+            .extend_from_slice(&[TAG_SAMPLE_SPEC, format, channels]);
+        self.data.extend_from_slice(&rate.to_be_bytes());
+        self
+    }
+
+    pub fn put_channel_map(&mut self, positions: &[u8]) -> &mut Self {
+        self.data
+            .extend_from_slice(&[TAG_CHANNEL_MAP, positions.len() as u8]);
+        self.data.extend_from_slice(positions);
+        self
+    }
+
+    pub fn put_cvolume(&mut self, volumes: &[u32]) -> &mut Self {
+        self.data
+            .extend_from_slice(&[TAG_CVOLUME, volumes.len() as u8]);
+        for volume in volumes {
+            self.data.extend_from_slice(&volume.to_be_bytes());
+        }
+        self
+    }
+
+    pub fn put_volume(&mut self, volume: u32) -> &mut Self {
+        self.data.push(TAG_VOLUME);
+        self.data.extend_from_slice(&volume.to_be_bytes());
+        self
+    }
+
+    /// String properties; values are sent NUL-terminated like libpulse does
+    pub fn put_proplist(&mut self, properties: &[(&str, &str)]) -> &mut Self {
+        self.data.push(TAG_PROPLIST);
+        for (key, value) in properties {
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.push(0);
+            self.put_string(Some(key));
+            self.put_u32(bytes.len() as u32);
+            self.put_arbitrary(&bytes);
+        }
+        self.put_string(None)
+    }
+
+    /// Stream encoding (e.g. PCM or AC3) and its properties
+    pub fn put_format_info(&mut self, encoding: u8, properties: &[(&str, &str)]) -> &mut Self {
+        self.data.push(TAG_FORMAT_INFO);
+        self.put_u8(encoding);
+        self.put_proplist(properties)
+    }
+
+    pub fn get_u32(&mut self) -> Result<u32> {
+        self.expect_tag(TAG_U32)?;
+        Ok(u32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn get_u8(&mut self) -> Result<u8> {
+        self.expect_tag(TAG_U8)?;
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn get_usec(&mut self) -> Result<u64> {
+        self.expect_tag(TAG_USEC)?;
+        Ok(u64::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn get_bool(&mut self) -> Result<bool> {
+        match self.take(1)?[0] {
+            TAG_BOOLEAN_TRUE => Ok(true),
+            TAG_BOOLEAN_FALSE => Ok(false),
+            tag => Err(self.unexpected(tag, TAG_BOOLEAN_TRUE)),
+        }
+    }
+
+    pub fn get_string(&mut self) -> Result<Option<String>> {
+        match self.take(1)?[0] {
+            TAG_STRING_NULL => Ok(None),
+            TAG_STRING => {
+                let rest = &self.data[self.position..];
+                let end = rest
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(|| eyre!("Unterminated string in tagstruct"))?;
+                let value = String::from_utf8_lossy(&rest[..end]).into_owned();
+                self.position += end + 1;
+                Ok(Some(value))
+            }
+            tag => Err(self.unexpected(tag, TAG_STRING)),
+        }
+    }
+
+    pub fn get_arbitrary(&mut self) -> Result<Vec<u8>> {
+        self.expect_tag(TAG_ARBITRARY)?;
+        let length = u32::from_be_bytes(self.take_array()?) as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    pub fn get_sample_spec(&mut self) -> Result<(u8, u8, u32)> {
+        self.expect_tag(TAG_SAMPLE_SPEC)?;
+        let [format, channels] = self.take_array()?;
+        Ok((format, channels, u32::from_be_bytes(self.take_array()?)))
+    }
+
+    pub fn get_channel_map(&mut self) -> Result<Vec<u8>> {
+        self.expect_tag(TAG_CHANNEL_MAP)?;
+        let channels = self.take(1)?[0] as usize;
+        Ok(self.take(channels)?.to_vec())
+    }
+
+    pub fn get_cvolume(&mut self) -> Result<Vec<u32>> {
+        self.expect_tag(TAG_CVOLUME)?;
+        let channels = self.take(1)?[0] as usize;
+        (0..channels)
+            .map(|_| Ok(u32::from_be_bytes(self.take_array()?)))
+            .collect()
+    }
+
+    pub fn get_volume(&mut self) -> Result<u32> {
+        self.expect_tag(TAG_VOLUME)?;
+        Ok(u32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn get_proplist(&mut self) -> Result<HashMap<String, String>> {
+        self.expect_tag(TAG_PROPLIST)?;
+        let mut properties = HashMap::new();
+        while let Some(key) = self.get_string()? {
+            let length = self.get_u32()? as usize;
+            let value = self.get_arbitrary()?;
+            if value.len() != length {
+                return Err(eyre!("Property {} has a mismatched length", key));
+            }
+            let value = value.strip_suffix(&[0]).unwrap_or(&value);
+            properties.insert(key, String::from_utf8_lossy(value).into_owned());
+        }
+        Ok(properties)
+    }
+
+    pub fn get_format_info(&mut self) -> Result<(u8, HashMap<String, String>)> {
+        self.expect_tag(TAG_FORMAT_INFO)?;
+        Ok((self.get_u8()?, self.get_proplist()?))
+    }
+
+    fn expect_tag(&mut self, expected: u8) -> Result<()> {
+        let tag = self.take(1)?[0];
+        if tag == expected {
+            Ok(())
+        } else {
+            Err(self.unexpected(tag, expected))
+        }
+    }
+
+    fn unexpected(&self, tag: u8, expected: u8) -> color_eyre::Report {
+        eyre!(
+            "Expected tag '{}' but found '{}' at offset {}",
+            expected as char,
+            tag as char,
+            self.position - 1
+        )
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8]> {
+        let end = self.position + length;
+        if end > self.data.len() {
+            return Err(eyre!("Tagstruct ended early at offset {}", self.position));
+        }
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut tagstruct = TagStruct::new();
+        tagstruct
+            .put_u32(35)
+            .put_string(Some("alsa_output.pci"))
+            .put_string(None)
+            .put_bool(true)
+            .put_sample_spec(3, 2, 48000)
+            .put_channel_map(&[1, 2])
+            .put_cvolume(&[0x10000, 0x8000])
+            .put_proplist(&[("device.bus", "bluetooth")])
+            .put_format_info(1, &[])
+            .put_usec(42);
+
+        let mut tagstruct = TagStruct::from_bytes(tagstruct.into_bytes());
+        assert_eq!(tagstruct.get_u32().unwrap(), 35);
+        assert_eq!(
+            tagstruct.get_string().unwrap().as_deref(),
+            Some("alsa_output.pci")
+        );
+        assert_eq!(tagstruct.get_string().unwrap(), None);
+        assert!(tagstruct.get_bool().unwrap());
+        assert_eq!(tagstruct.get_sample_spec().unwrap(), (3, 2, 48000));
+        assert_eq!(tagstruct.get_channel_map().unwrap(), vec![1, 2]);
+        assert_eq!(tagstruct.get_cvolume().unwrap(), vec![0x10000, 0x8000]);
+        assert_eq!(tagstruct.get_proplist().unwrap()["device.bus"], "bluetooth");
+        assert_eq!(tagstruct.get_format_info().unwrap().0, 1);
+        assert_eq!(tagstruct.get_usec().unwrap(), 42);
+        assert!(tagstruct.get_u32().is_err());
+    }
+
+    #[test]
+    fn test_wrong_tag() {
+        let mut tagstruct = TagStruct::new();
+        tagstruct.put_string(Some("x"));
+        let mut tagstruct = TagStruct::from_bytes(tagstruct.into_bytes());
+        assert!(tagstruct.get_u32().is_err());
+    }
+}
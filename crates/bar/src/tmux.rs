@@ -0,0 +1,343 @@
+use color_eyre::eyre::eyre;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::ChildStdin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::{command, logging, wakeup};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// tmux has no notifications for activity and bell flags, so those are polled
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Process tree depth searched when matching a terminal to its tmux client
+const MAX_ANCESTORS: usize = 32;
+
+/// Session names can't contain ':', so only the trailing window name needs care
+const REFRESH_COMMAND: &str = "list-clients -F 'client:#{client_control_mode}:#{client_activity}:#{client_pid}:#{session_name}' ; \
+list-windows -a -F 'window:#{session_name}:#{window_index}:#{window_active}:#{window_activity_flag}:#{window_bell_flag}:#{window_id}:#{window_name}'\n";
+
+static SHARED: LazyLock<TmuxControl> = LazyLock::new(TmuxControl::start);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmuxWindow {
+    /// Server-wide window id, e.g. `@3`
+    pub id: String,
+    pub session: String,
+    pub index: u32,
+    pub name: String,
+    pub active: bool,
+    pub activity: bool,
+    pub bell: bool,
+}
+
+/// A terminal attached to the tmux server; control mode clients, ours included, are left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmuxClient {
+    pub pid: u32,
+    pub session: String,
+    /// Time of the client's last input, in seconds since the epoch
+    pub activity: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TmuxState {
+    pub clients: Vec<TmuxClient>,
+    pub windows: Vec<TmuxWindow>,
+}
+
+impl TmuxState {
+    /// Session of the most recently used client
+    pub fn attached_session(&self) -> Option<&str> {
+        self.clients
+            .iter()
+            .max_by_key(|c| c.activity)
+            .map(|c| c.session.as_str())
+    }
+
+    /// Session of the tmux client running somewhere below `pid`, e.g. a terminal emulator
+    pub fn session_for_pid(&self, pid: u32, proc_root: &Path) -> Option<&str> {
+        self.clients
+            .iter()
+            .find(|c| is_descendant(c.pid, pid, proc_root))
+            .map(|c| c.session.as_str())
+    }
+
+    pub fn windows_in<'a>(&'a self, session: &'a str) -> impl Iterator<Item = &'a TmuxWindow> {
+        self.windows.iter().filter(move |w| w.session == session)
+    }
+
+    /// Apply the output of one command block; blocks from other commands are ignored
+    fn apply(&mut self, lines: &[String]) {
+        if lines.iter().any(|l| l.starts_with("client:")) {
+            self.clients = lines.iter().filter_map(|l| parse_client(l)).collect();
+        }
+        if lines.iter().any(|l| l.starts_with("window:")) {
+            self.windows = lines.iter().filter_map(|l| parse_window(l)).collect();
+        }
+    }
+}
+
+fn parse_client(line: &str) -> Option<TmuxClient> {
+    let mut fields = line.strip_prefix("client:")?.splitn(4, ':');
+    let control_mode = fields.next()? == "1";
+    let activity = fields.next()?.parse().ok()?;
+    let pid = fields.next()?.parse().ok()?;
+    let session = fields.next()?.to_string();
+    (!control_mode).then_some(TmuxClient {
+        pid,
+        session,
+        activity,
+    })
+}
+
+fn parse_window(line: &str) -> Option<TmuxWindow> {
+    let mut fields = line.strip_prefix("window:")?.splitn(7, ':');
+    Some(TmuxWindow {
+        session: fields.next()?.to_string(),
+        index: fields.next()?.parse().ok()?,
+        active: fields.next()? == "1",
+        activity: fields.next()? == "1",
+        bell: fields.next()? == "1",
+        id: fields.next()?.to_string(),
+        name: fields.next()?.to_string(),
+    })
+}
+
+fn is_descendant(pid: u32, ancestor: u32, proc_root: &Path) -> bool {
+    let mut pid = pid;
+    for _ in 0..MAX_ANCESTORS {
+        if pid == ancestor {
+            return true;
+        }
+        match parent_pid(pid, proc_root) {
+            Some(parent) if parent > 1 => pid = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn parent_pid(pid: u32, proc_root: &Path) -> Option<u32> {
+    let stat = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    // The command name may contain spaces and parentheses; fields resume after the last ')'
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// A background `tmux -C` client whose notifications keep a [`TmuxState`] current
+#[derive(Debug)]
+pub struct TmuxControl {
+    state: Arc<Mutex<TmuxState>>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+}
+
+/// The control connection shared by all tmux components, started on first use
+pub fn shared() -> &'static TmuxControl {
+    &SHARED
+}
+
+impl TmuxControl {
+    fn start() -> Self {
+        let control = Self {
+            state: Arc::new(Mutex::new(TmuxState::default())),
+            stdin: Arc::new(Mutex::new(None)),
+        };
+
+        let state = control.state.clone();
+        let stdin = control.stdin.clone();
+        std::thread::spawn(move || {
+            loop {
+                match run_control_client(&state, &stdin) {
+                    Err(e) if is_not_found(&e) => {
+                        logging::log_component_error("TMUX", "tmux is not installed");
+                        return;
+                    }
+                    Err(e) => logging::log_component_error("TMUX", &format!("{}", e)),
+                    Ok(()) => {}
+                }
+
+                // Without a server there is nothing to show
+                if let Ok(mut state) = state.lock() {
+                    *state = TmuxState::default();
+                }
+                wakeup::notify();
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        });
+
+        control
+    }
+
+    pub fn state(&self) -> TmuxState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// Make `window_id` the current window of its session
+    pub fn select_window(&self, window_id: &str) -> color_eyre::Result<()> {
+        send(&self.stdin, &format!("select-window -t {}\n", window_id))
+    }
+}
+
+fn is_not_found(e: &color_eyre::Report) -> bool {
+    e.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+fn send(stdin: &Mutex<Option<ChildStdin>>, command: &str) -> color_eyre::Result<()> {
+    let mut stdin = stdin
+        .lock()
+        .map_err(|_| eyre!("tmux stdin lock poisoned"))?;
+    let stdin = stdin
+        .as_mut()
+        .ok_or_else(|| eyre!("Not connected to tmux"))?;
+    stdin.write_all(command.as_bytes())?;
+    stdin.flush()?;
+    Ok(())
+}
+
+/// Attach in control mode and follow notifications until the client exits, e.g. when the
+/// server has no sessions or shuts down
+fn run_control_client(
+    state: &Mutex<TmuxState>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
+) -> color_eyre::Result<()> {
+    // Without ignore-size the control client would shrink windows to its default 80x24
+    let mut child = command::attach(
+        "tmux",
+        &["-C", "attach-session", "-f", "no-output,ignore-size"],
+    )?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre!("tmux stdout unavailable"))?;
+    *stdin
+        .lock()
+        .map_err(|_| eyre!("tmux stdin lock poisoned"))? = child.stdin.take();
+
+    let connected = Arc::new(AtomicBool::new(true));
+    spawn_poller(stdin.clone(), connected.clone());
+    let result = follow_notifications(BufReader::new(stdout), state, stdin);
+
+    connected.store(false, Ordering::Relaxed);
+    if let Ok(mut stdin) = stdin.lock() {
+        *stdin = None;
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+fn spawn_poller(stdin: Arc<Mutex<Option<ChildStdin>>>, connected: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        while connected.load(Ordering::Relaxed) {
+            if send(&stdin, REFRESH_COMMAND).is_err() {
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn follow_notifications(
+    reader: impl BufRead,
+    state: &Mutex<TmuxState>,
+    stdin: &Mutex<Option<ChildStdin>>,
+) -> color_eyre::Result<()> {
+    let mut block: Option<Vec<String>> = None;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("%begin") {
+            block = Some(Vec::new());
+        } else if line.starts_with("%end") {
+            if let Some(lines) = block.take()
+                && let Ok(mut state) = state.lock()
+            {
+                let previous = state.clone();
+                state.apply(&lines);
+                if *state != previous {
+                    wakeup::notify();
+                }
+            }
+        } else if line.starts_with("%error") {
+            if let Some(lines) = block.take() {
+                logging::log_component_error("TMUX", &lines.join(" "));
+            }
+        } else if line.starts_with("%exit") {
+            return Ok(());
+        } else if let Some(lines) = block.as_mut() {
+            lines.push(line);
+        } else if line.starts_with('%') {
+            // Any window or session change; re-list rather than tracking each kind
+            send(stdin, REFRESH_COMMAND)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_apply_blocks() {
+        let mut state = TmuxState::default();
+        state.apply(&lines(&[
+            "client:1:1792348400:900:work",
+            "client:0:1792348300:200:work",
+            "client:0:1792348351:300:notes",
+        ]));
+        state.apply(&lines(&[
+            "window:work:0:1:0:0:@1:vim",
+            "window:work:1:0:1:0:@2:my shell:x",
+            "window:notes:0:1:0:1:@3:todo",
+        ]));
+        // Empty blocks, like the reply to select-window, leave the state alone
+        state.apply(&[]);
+
+        assert_eq!(state.clients.len(), 2);
+        assert_eq!(state.attached_session(), Some("notes"));
+
+        let work: Vec<_> = state.windows_in("work").collect();
+        assert_eq!(work.len(), 2);
+        assert_eq!(work[1].name, "my shell:x");
+        assert!(work[1].activity && !work[1].active);
+        assert_eq!(work[1].id, "@2");
+        assert!(state.windows_in("notes").next().unwrap().bell);
+    }
+
+    #[test]
+    fn test_session_for_pid() {
+        let proc_root = tempfile::tempdir().unwrap();
+        // kitty (100) -> zsh (101) -> tmux client (102)
+        for (pid, stat) in [
+            (100, "100 (kitty) S 1 100"),
+            (101, "101 (zsh) S 100 101"),
+            (102, "102 (tmux: client) S 101 102"),
+            (200, "200 (foot) S 1 200"),
+        ] {
+            let dir = proc_root.path().join(pid.to_string());
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("stat"), stat).unwrap();
+        }
+
+        let state = TmuxState {
+            clients: vec![TmuxClient {
+                pid: 102,
+                session: "work".to_string(),
+                activity: 0,
+            }],
+            windows: vec![],
+        };
+        assert_eq!(state.session_for_pid(100, proc_root.path()), Some("work"));
+        assert_eq!(state.session_for_pid(200, proc_root.path()), None);
+    }
+}
@@ -0,0 +1,18 @@
+//! Waking the main loop when state changes in the background
+//!
+//! Background threads and tasks call [`notify`] after storing new state; the main loop waits
+//! on [`wait`] alongside input and its next deadline, so nothing is redrawn while idle.
+
+use tokio::sync::Notify;
+
+static WAKEUP: Notify = Notify::const_new();
+
+/// Ask the main loop to update and, if anything changed, redraw
+pub fn notify() {
+    // Stores a permit if the loop is busy, so the request isn't lost
+    WAKEUP.notify_one();
+}
+
+pub async fn wait() {
+    WAKEUP.notified().await;
+}
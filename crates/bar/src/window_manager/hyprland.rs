@@ -0,0 +1,384 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Window, WindowManager, WmState, Workspace};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Events from `.socket2.sock` that invalidate our cached state
+const STATE_EVENTS: &[&str] = &[
+    "workspace",
+    "workspacev2",
+    "focusedmon",
+    "focusedmonv2",
+    "activewindow",
+    "activewindowv2",
+    "openwindow",
+    "closewindow",
+    "movewindow",
+    "movewindowv2",
+    "windowtitle",
+    "windowtitlev2",
+    "createworkspace",
+    "createworkspacev2",
+    "destroyworkspace",
+    "destroyworkspacev2",
+    "renameworkspace",
+    "urgent",
+    "activespecial",
+    "activespecialv2",
+];
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct HyprWorkspace {
+    id: i32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    monitor: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct HyprMonitor {
+    #[serde(rename = "activeWorkspace")]
+    active_workspace: HyprWorkspace,
+    /// Id is 0 when no special workspace is toggled on this monitor
+    #[serde(rename = "specialWorkspace", default)]
+    special_workspace: HyprWorkspace,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct HyprClient {
+    address: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    pid: i64,
+    workspace: HyprWorkspace,
+}
+
+/// Client for Hyprland's request (`.socket.sock`) and event (`.socket2.sock`) sockets.
+///
+/// State is fetched once and then refreshed whenever a relevant event arrives, so
+/// components can read it every tick without talking to the compositor.
+#[derive(Debug)]
+pub struct Hyprland {
+    socket_dir: PathBuf,
+    state: Arc<Mutex<WmState>>,
+    /// Addresses of windows that raised `urgent>>` and haven't been visited since
+    urgent: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Hyprland {
+    /// Locate the sockets of the running Hyprland instance
+    pub fn from_env() -> Option<Self> {
+        let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok()?;
+        Some(Self::with_socket_dir(
+            PathBuf::from(runtime_dir).join("hypr").join(signature),
+        ))
+    }
+
+    pub fn with_socket_dir(socket_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_dir: socket_dir.into(),
+            state: Arc::new(Mutex::new(WmState::default())),
+            urgent: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Send a raw request (e.g. `j/clients`) and return the response body
+    pub fn request(&self, command: &str) -> color_eyre::Result<String> {
+        request(&self.socket_dir, command)
+    }
+
+    /// Re-fetch the full state over the request socket
+    pub fn refresh(&self) {
+        refresh(&self.socket_dir, &self.state, &self.urgent);
+    }
+
+    /// Listen on the event socket in the background, refreshing state on changes.
+    ///
+    /// The listener reconnects if the socket drops and exits once this client is gone.
+    pub fn start_event_listener(&self) {
+        let socket_dir = self.socket_dir.clone();
+        let urgent = self.urgent.clone();
+
+        super::spawn_event_loop("HYPRLAND", Arc::downgrade(&self.state), move |state| {
+            let stream = UnixStream::connect(socket_dir.join(".socket2.sock"))?;
+            for line in BufReader::new(stream).lines() {
+                let line = line?;
+                let Some(state) = state.upgrade() else {
+                    return Ok(());
+                };
+
+                if let Some(address) = line.strip_prefix("urgent>>")
+                    && let Ok(mut urgent) = urgent.lock()
+                {
+                    urgent.insert(format!("0x{}", address));
+                }
+
+                if is_state_event(&line) {
+                    refresh(&socket_dir, &state, &urgent);
+                }
+            }
+            Err(color_eyre::eyre::eyre!("Event socket closed"))
+        });
+    }
+}
+
+impl WindowManager for Hyprland {
+    fn name(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn state(&self) -> WmState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+fn request(socket_dir: &Path, command: &str) -> color_eyre::Result<String> {
+    let mut stream = UnixStream::connect(socket_dir.join(".socket.sock"))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.write_all(command.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+fn request_json<T: serde::de::DeserializeOwned>(
+    socket_dir: &Path,
+    command: &str,
+) -> color_eyre::Result<T> {
+    let response = request(socket_dir, &format!("j/{}", command))?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Hyprland answers `activewindow`/`activeworkspace` with `{}` when there is none
+fn request_optional<T: serde::de::DeserializeOwned>(
+    socket_dir: &Path,
+    command: &str,
+) -> color_eyre::Result<Option<T>> {
+    let value: serde_json::Value = request_json(socket_dir, command)?;
+    if value.as_object().is_some_and(|o| o.is_empty()) {
+        return Ok(None);
+    }
+    Ok(serde_json::from_value(value).ok())
+}
+
+fn fetch_state(socket_dir: &Path) -> color_eyre::Result<WmState> {
+    let workspaces: Vec<HyprWorkspace> = request_json(socket_dir, "workspaces")?;
+    let monitors: Vec<HyprMonitor> = request_json(socket_dir, "monitors")?;
+    let clients: Vec<HyprClient> = request_json(socket_dir, "clients")?;
+    let active_workspace: Option<HyprWorkspace> = request_optional(socket_dir, "activeworkspace")?;
+    let active_window: Option<HyprClient> = request_optional(socket_dir, "activewindow")?;
+
+    Ok(WmState {
+        workspaces: workspaces
+            .into_iter()
+            .map(|w| Workspace {
+                id: w.id as i64,
+                number: w.id,
+                // Named workspaces have negative ids too, so go by the prefix
+                special: w.name.starts_with("special:"),
+                visible: monitors
+                    .iter()
+                    .any(|m| m.active_workspace.id == w.id || m.special_workspace.id == w.id),
+                name: w.name,
+                output: w.monitor,
+            })
+            .collect(),
+        windows: clients
+            .into_iter()
+            .map(|c| Window {
+                id: c.address,
+                class: c.class,
+                title: c.title,
+                pid: u32::try_from(c.pid).ok(),
+                workspace_id: c.workspace.id as i64,
+                urgent: false,
+            })
+            .collect(),
+        focused_workspace: active_workspace.map(|w| w.id as i64),
+        focused_window: active_window.map(|c| c.address),
+    })
+}
+
+fn refresh(socket_dir: &Path, state: &Mutex<WmState>, urgent: &Mutex<HashSet<String>>) {
+    let fetched = fetch_state(socket_dir).map(|mut new_state| {
+        if let Ok(mut urgent) = urgent.lock() {
+            apply_urgent(&mut new_state, &mut urgent);
+        }
+        new_state
+    });
+    super::store_state("HYPRLAND", state, fetched);
+}
+
+/// Hyprland doesn't report urgency in `clients`, so mark windows from the `urgent>>`
+/// events we've seen, forgetting them once their workspace is visited or they close
+fn apply_urgent(state: &mut WmState, urgent: &mut HashSet<String>) {
+    urgent.retain(|address| {
+        state
+            .windows
+            .iter()
+            .any(|w| &w.id == address && Some(w.workspace_id) != state.focused_workspace)
+    });
+
+    for window in &mut state.windows {
+        window.urgent = urgent.contains(&window.id);
+    }
+}
+
+fn is_state_event(line: &str) -> bool {
+    line.split_once(">>")
+        .is_some_and(|(event, _)| STATE_EVENTS.contains(&event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::time::Instant;
+
+    /// Serve canned JSON on `.socket.sock`, keyed by request
+    fn spawn_fake_request_socket(dir: &Path, responses: Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = UnixListener::bind(dir.join(".socket.sock")).unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut buf = [0u8; 256];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let command = String::from_utf8_lossy(&buf[..n]).to_string();
+                let response = responses
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|(c, _)| *c == command)
+                    .map(|(_, r)| r.clone())
+                    .unwrap_or_else(|| "unknown request".to_string());
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+    }
+
+    fn responses(active_workspace: i32) -> Vec<(String, String)> {
+        vec![
+            (
+                "j/workspaces".to_string(),
+                r#"[{"id":2,"name":"2","monitor":"HDMI-A-1"},{"id":1,"name":"1","monitor":"eDP-1"},{"id":-98,"name":"special:term","monitor":"eDP-1"},{"id":-1337,"name":"mail","monitor":"HDMI-A-1"}]"#
+                    .to_string(),
+            ),
+            (
+                "j/monitors".to_string(),
+                r#"[{"name":"eDP-1","activeWorkspace":{"id":1,"name":"1"},"specialWorkspace":{"id":-98,"name":"special:term"}},{"name":"HDMI-A-1","activeWorkspace":{"id":2,"name":"2"},"specialWorkspace":{"id":0,"name":""}}]"#
+                    .to_string(),
+            ),
+            (
+                "j/clients".to_string(),
+                r#"[{"address":"0xabc","class":"kitty","title":"nvim","pid":42,"workspace":{"id":1,"name":"1"}}]"#
+                    .to_string(),
+            ),
+            (
+                "j/activeworkspace".to_string(),
+                format!(r#"{{"id":{0},"name":"{0}"}}"#, active_workspace),
+            ),
+            ("j/activewindow".to_string(), "{}".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_refresh_reads_request_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        spawn_fake_request_socket(dir.path(), Arc::new(Mutex::new(responses(1))));
+
+        let hyprland = Hyprland::with_socket_dir(dir.path());
+        hyprland.refresh();
+
+        let state = hyprland.state();
+        assert_eq!(state.workspaces.len(), 4);
+        assert!(state.workspace(-98).is_some_and(|w| w.special));
+        assert!(
+            state
+                .workspace(-1337)
+                .is_some_and(|w| !w.special && w.name == "mail")
+        );
+        assert_eq!(state.windows[0].class, "kitty");
+        assert_eq!(state.windows[0].pid, Some(42));
+        assert_eq!(state.windows[0].workspace_id, 1);
+        assert_eq!(state.focused_workspace, Some(1));
+        assert_eq!(state.focused_output(), Some("eDP-1"));
+        assert!(
+            state
+                .workspaces
+                .iter()
+                .all(|w| w.visible == (w.id != -1337))
+        );
+        assert!(state.focused_window.is_none());
+    }
+
+    #[test]
+    fn test_events_trigger_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let canned = Arc::new(Mutex::new(responses(1)));
+        spawn_fake_request_socket(dir.path(), canned.clone());
+
+        let events = UnixListener::bind(dir.path().join(".socket2.sock")).unwrap();
+
+        let hyprland = Hyprland::with_socket_dir(dir.path());
+        hyprland.refresh();
+        hyprland.start_event_listener();
+
+        let (mut event_stream, _) = events.accept().unwrap();
+        *canned.lock().unwrap() = responses(2);
+        event_stream.write_all(b"workspace>>2\n").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while hyprland.state().focused_workspace != Some(2) {
+            assert!(Instant::now() < deadline, "state was not refreshed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_urgent_cleared_when_workspace_visited() {
+        let window = |id: &str, workspace_id| Window {
+            id: id.to_string(),
+            workspace_id,
+            ..Default::default()
+        };
+        let mut state = WmState {
+            windows: vec![window("0x1", 1), window("0x2", 2)],
+            focused_workspace: Some(1),
+            ..Default::default()
+        };
+        let mut urgent: HashSet<String> = ["0x2".to_string(), "0xgone".to_string()].into();
+
+        apply_urgent(&mut state, &mut urgent);
+        assert!(state.windows[1].urgent);
+        assert!(!state.windows[0].urgent);
+        assert_eq!(urgent.len(), 1);
+
+        state.focused_workspace = Some(2);
+        apply_urgent(&mut state, &mut urgent);
+        assert!(!state.windows[1].urgent);
+        assert!(urgent.is_empty());
+    }
+
+    #[test]
+    fn test_is_state_event() {
+        assert!(is_state_event("openwindow>>abc,1,kitty,title"));
+        assert!(is_state_event("urgent>>abc"));
+        assert!(is_state_event("activespecial>>special:scratch,DP-1"));
+        assert!(!is_state_event("submap>>resize"));
+        assert!(!is_state_event("garbage"));
+    }
+}
@@ -0,0 +1,187 @@
+pub mod hyprland;
+pub mod niri;
+pub mod sway;
+
+pub use hyprland::Hyprland;
+pub use niri::Niri;
+pub use sway::Sway;
+
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
+
+use crate::{logging, wakeup};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The running backend, kept alive by the components subscribed to it
+static CURRENT: Mutex<Option<Weak<dyn WindowManager>>> = Mutex::new(None);
+
+/// Output that was focused when the bar started, i.e. the one the panel was spawned on
+static STARTUP_OUTPUT: LazyLock<Option<String>> =
+    LazyLock::new(|| subscribe()?.state().focused_output().map(str::to_string));
+
+/// A workspace as reported by the compositor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspace {
+    /// Backend-specific identifier, referenced by [`Window::workspace_id`]
+    pub id: i64,
+    /// Position used for ordering and coloring
+    pub number: i32,
+    pub name: String,
+    pub output: String,
+    /// Currently shown on its output (not necessarily focused)
+    pub visible: bool,
+    /// Scratchpad-style workspace outside the normal sequence
+    pub special: bool,
+}
+
+/// A toplevel window as reported by the compositor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Window {
+    /// Backend-specific identifier (Hyprland address, sway con id, ...)
+    pub id: String,
+    /// Window class or Wayland app id
+    pub class: String,
+    pub title: String,
+    pub pid: Option<u32>,
+    pub workspace_id: i64,
+    /// Demanding attention
+    pub urgent: bool,
+}
+
+/// Snapshot of the compositor state shared by the workspace and window components
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WmState {
+    pub workspaces: Vec<Workspace>,
+    pub windows: Vec<Window>,
+    pub focused_workspace: Option<i64>,
+    pub focused_window: Option<String>,
+}
+
+impl WmState {
+    pub fn workspace(&self, id: i64) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.id == id)
+    }
+
+    pub fn focused_window(&self) -> Option<&Window> {
+        let focused = self.focused_window.as_ref()?;
+        self.windows.iter().find(|w| &w.id == focused)
+    }
+
+    pub fn windows_on(&self, workspace_id: i64) -> impl Iterator<Item = &Window> {
+        self.windows
+            .iter()
+            .filter(move |w| w.workspace_id == workspace_id)
+    }
+
+    pub fn focused_output(&self) -> Option<&str> {
+        let focused = self.workspace(self.focused_workspace?)?;
+        Some(&focused.output)
+    }
+
+    /// Whether a workspace should be shown for the given output filter (`None` means all)
+    pub fn on_output(&self, workspace_id: i64, output: Option<&str>) -> bool {
+        match output {
+            None => true,
+            Some(output) => self
+                .workspace(workspace_id)
+                .is_some_and(|w| w.output == output),
+        }
+    }
+}
+
+/// Resolve a component's `monitor` option into an output name, or `None` for all outputs.
+///
+/// `"auto"` (the default) picks the output the bar was started on, `"all"` disables
+/// filtering and anything else is taken as an output name.
+pub fn resolve_output(monitor: Option<&str>) -> Option<String> {
+    match monitor.unwrap_or("auto") {
+        "all" => None,
+        "auto" => STARTUP_OUTPUT.clone(),
+        name => Some(name.to_string()),
+    }
+}
+
+/// A compositor backend that keeps a [`WmState`] up to date
+pub trait WindowManager: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The most recent state, updated in the background from compositor events
+    fn state(&self) -> WmState;
+}
+
+/// The window manager backend for this session, or `None` if none was detected.
+///
+/// Components hold on to the returned handle; once the last one is dropped the backend stops
+/// listening for events, and the next subscriber starts a fresh one.
+pub fn subscribe() -> Option<Arc<dyn WindowManager>> {
+    let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(backend) = current.as_ref().and_then(Weak::upgrade) {
+        return Some(backend);
+    }
+
+    let backend = detect()?;
+    *current = Some(Arc::downgrade(&backend));
+    Some(backend)
+}
+
+/// Pick a backend from the environment and start listening for its events
+pub fn detect() -> Option<Arc<dyn WindowManager>> {
+    if let Some(hyprland) = Hyprland::from_env() {
+        hyprland.refresh();
+        hyprland.start_event_listener();
+        return Some(Arc::new(hyprland));
+    }
+
+    if let Some(sway) = Sway::from_env() {
+        sway.refresh();
+        sway.start_event_listener();
+        return Some(Arc::new(sway));
+    }
+
+    if let Some(niri) = Niri::from_env() {
+        niri.refresh();
+        niri.start_event_listener();
+        return Some(Arc::new(niri));
+    }
+
+    None
+}
+
+/// Run `listen` on a background thread, reconnecting after failures until `state` is dropped
+pub(crate) fn spawn_event_loop<F>(name: &'static str, state: Weak<Mutex<WmState>>, mut listen: F)
+where
+    F: FnMut(&Weak<Mutex<WmState>>) -> color_eyre::Result<()> + Send + 'static,
+{
+    std::thread::spawn(move || {
+        loop {
+            if let Err(e) = listen(&state) {
+                logging::log_component_error(name, &format!("Event stream failed: {}", e));
+            }
+
+            if state.strong_count() == 0 {
+                return;
+            }
+            std::thread::sleep(RECONNECT_DELAY);
+        }
+    });
+}
+
+/// Replace `state` with a freshly fetched snapshot, logging failures
+pub(crate) fn store_state(
+    name: &str,
+    state: &Mutex<WmState>,
+    fetched: color_eyre::Result<WmState>,
+) {
+    match fetched {
+        Ok(new_state) => {
+            if let Ok(mut state) = state.lock()
+                && *state != new_state
+            {
+                *state = new_state;
+                wakeup::notify();
+            }
+        }
+        Err(e) => logging::log_component_error(name, &format!("{}", e)),
+    }
+}
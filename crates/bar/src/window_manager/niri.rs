@@ -0,0 +1,277 @@
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Window, WindowManager, WmState, Workspace};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize, Debug)]
+struct NiriWorkspace {
+    id: u64,
+    /// Index of the workspace on its output, starting at 1
+    idx: u8,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    output: Option<String>,
+    /// Shown on its output
+    #[serde(default)]
+    is_active: bool,
+    #[serde(default)]
+    is_focused: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct NiriWindow {
+    id: u64,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    app_id: Option<String>,
+    #[serde(default)]
+    pid: Option<i32>,
+    #[serde(default)]
+    workspace_id: Option<u64>,
+    #[serde(default)]
+    is_focused: bool,
+    #[serde(default)]
+    is_urgent: bool,
+}
+
+/// niri backend using the JSON IPC socket at `$NIRI_SOCKET`
+#[derive(Debug)]
+pub struct Niri {
+    socket_path: PathBuf,
+    state: Arc<Mutex<WmState>>,
+}
+
+impl Niri {
+    pub fn from_env() -> Option<Self> {
+        let socket_path = std::env::var("NIRI_SOCKET").ok()?;
+        Some(Self::with_socket_path(socket_path))
+    }
+
+    pub fn with_socket_path(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            state: Arc::new(Mutex::new(WmState::default())),
+        }
+    }
+
+    /// Re-fetch workspaces and windows
+    pub fn refresh(&self) {
+        super::store_state("NIRI", &self.state, fetch_state(&self.socket_path));
+    }
+
+    /// Follow the event stream, refreshing state whenever an event arrives
+    pub fn start_event_listener(&self) {
+        let socket_path = self.socket_path.clone();
+
+        super::spawn_event_loop("NIRI", Arc::downgrade(&self.state), move |state| {
+            let mut stream = UnixStream::connect(&socket_path)?;
+            stream.write_all(b"\"EventStream\"\n")?;
+
+            let mut lines = BufReader::new(stream).lines();
+            let reply = lines
+                .next()
+                .ok_or_else(|| color_eyre::eyre::eyre!("Event stream closed"))??;
+            parse_reply(&reply)?;
+
+            for line in lines {
+                line?;
+                let Some(state) = state.upgrade() else {
+                    return Ok(());
+                };
+                super::store_state("NIRI", &state, fetch_state(&socket_path));
+            }
+            Err(color_eyre::eyre::eyre!("Event stream closed"))
+        });
+    }
+}
+
+impl WindowManager for Niri {
+    fn name(&self) -> &'static str {
+        "niri"
+    }
+
+    fn state(&self) -> WmState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+/// Unwrap niri's `{"Ok": ...}` / `{"Err": "..."}` reply envelope
+fn parse_reply(line: &str) -> color_eyre::Result<serde_json::Value> {
+    let mut reply: serde_json::Value = serde_json::from_str(line)?;
+    if let Some(ok) = reply.get_mut("Ok") {
+        return Ok(ok.take());
+    }
+    let error = reply.get("Err").and_then(|e| e.as_str()).unwrap_or(line);
+    Err(color_eyre::eyre::eyre!("niri: {}", error))
+}
+
+/// Send a request such as `"Workspaces"` and return the named response payload
+fn request<T: serde::de::DeserializeOwned>(
+    socket_path: &Path,
+    name: &str,
+) -> color_eyre::Result<T> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    writeln!(stream, "\"{}\"", name)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let mut response = parse_reply(&line)?;
+    let payload = response
+        .get_mut(name)
+        .ok_or_else(|| color_eyre::eyre::eyre!("niri: unexpected response {}", line.trim()))?;
+    Ok(serde_json::from_value(payload.take())?)
+}
+
+fn fetch_state(socket_path: &Path) -> color_eyre::Result<WmState> {
+    let workspaces: Vec<NiriWorkspace> = request(socket_path, "Workspaces")?;
+    let windows: Vec<NiriWindow> = request(socket_path, "Windows")?;
+
+    Ok(WmState {
+        focused_workspace: workspaces
+            .iter()
+            .find(|w| w.is_focused)
+            .map(|w| w.id as i64),
+        focused_window: windows
+            .iter()
+            .find(|w| w.is_focused)
+            .map(|w| w.id.to_string()),
+        workspaces: workspaces
+            .into_iter()
+            .map(|w| Workspace {
+                id: w.id as i64,
+                number: w.idx as i32,
+                // Unnamed niri workspaces are known by their per-output index
+                name: w.name.unwrap_or_else(|| w.idx.to_string()),
+                output: w.output.unwrap_or_default(),
+                visible: w.is_active,
+                special: false,
+            })
+            .collect(),
+        windows: windows
+            .into_iter()
+            .filter_map(|w| {
+                Some(Window {
+                    id: w.id.to_string(),
+                    class: w.app_id.unwrap_or_default(),
+                    title: w.title.unwrap_or_default(),
+                    pid: w.pid.and_then(|p| u32::try_from(p).ok()),
+                    workspace_id: w.workspace_id? as i64,
+                    urgent: w.is_urgent,
+                })
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    fn workspaces(focused: u64) -> String {
+        format!(
+            r#"{{"Ok":{{"Workspaces":[
+                {{"id":5,"idx":1,"name":null,"output":"eDP-1","is_active":true,"is_focused":{}}},
+                {{"id":6,"idx":2,"name":"chat","output":"eDP-1","is_active":false,"is_focused":{}}},
+                {{"id":7,"idx":1,"name":null,"output":"HDMI-A-1","is_active":true,"is_focused":false}}
+            ]}}}}"#,
+            focused == 5,
+            focused == 6
+        )
+        .replace('\n', "")
+    }
+
+    const WINDOWS: &str = r#"{"Ok":{"Windows":[{"id":9,"title":"nvim","app_id":"kitty","pid":42,"workspace_id":5,"is_focused":true,"is_floating":false},{"id":10,"title":"Slack","app_id":"Slack","pid":null,"workspace_id":6,"is_focused":false,"is_floating":false,"is_urgent":true}]}}"#;
+
+    fn spawn_fake_niri(path: &Path, focused: Arc<Mutex<u64>>) -> mpsc::Receiver<UnixStream> {
+        let listener = UnixListener::bind(path).unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut line = String::new();
+                let _ = BufReader::new(&stream).read_line(&mut line);
+                let reply = match line.trim() {
+                    "\"Workspaces\"" => workspaces(*focused.lock().unwrap()),
+                    "\"Windows\"" => WINDOWS.to_string(),
+                    "\"EventStream\"" => r#"{"Ok":"Handled"}"#.to_string(),
+                    _ => r#"{"Err":"unknown request"}"#.to_string(),
+                };
+                let _ = writeln!(stream, "{}", reply);
+                if line.trim() == "\"EventStream\"" {
+                    let _ = tx.send(stream);
+                }
+            }
+        });
+        rx
+    }
+
+    #[test]
+    fn test_fetch_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("niri.sock");
+        let _events = spawn_fake_niri(&path, Arc::new(Mutex::new(5)));
+
+        let niri = Niri::with_socket_path(&path);
+        niri.refresh();
+        let state = niri.state();
+
+        assert_eq!(state.focused_workspace, Some(5));
+        let names: Vec<_> = state.workspaces.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, vec!["1", "chat", "1"]);
+        assert_eq!(state.workspaces[2].output, "HDMI-A-1");
+        assert!(state.workspaces[2].visible);
+        assert!(state.on_output(7, Some("HDMI-A-1")));
+        assert!(!state.on_output(5, Some("HDMI-A-1")));
+        assert!(state.on_output(5, None));
+
+        let focused = state.focused_window().unwrap();
+        assert_eq!(focused.class, "kitty");
+        assert_eq!(focused.pid, Some(42));
+        assert_eq!(state.workspace(focused.workspace_id).unwrap().number, 1);
+        assert!(state.windows_on(6).any(|w| w.urgent));
+    }
+
+    #[test]
+    fn test_events_trigger_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("niri.sock");
+        let focused = Arc::new(Mutex::new(5));
+        let events = spawn_fake_niri(&path, focused.clone());
+
+        let niri = Niri::with_socket_path(&path);
+        niri.refresh();
+        niri.start_event_listener();
+
+        let mut stream = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        *focused.lock().unwrap() = 6;
+        writeln!(
+            stream,
+            r#"{{"WorkspaceActivated":{{"id":6,"focused":true}}}}"#
+        )
+        .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while niri.state().focused_workspace != Some(6) {
+            assert!(Instant::now() < deadline, "state was not refreshed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_parse_reply_error() {
+        assert!(parse_reply(r#"{"Err":"no such request"}"#).is_err());
+    }
+}
@@ -0,0 +1,361 @@
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Window, WindowManager, WmState, Workspace};
+
+const MAGIC: &[u8; 6] = b"i3-ipc";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+
+/// Name of the hidden workspace holding scratchpad windows
+const SCRATCHPAD: &str = "__i3_scratch";
+
+#[derive(Deserialize, Debug)]
+struct I3Workspace {
+    id: i64,
+    num: i32,
+    name: String,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    visible: bool,
+    #[serde(default)]
+    output: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Node {
+    id: i64,
+    #[serde(rename = "type", default)]
+    node_type: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    urgent: bool,
+    #[serde(default)]
+    pid: Option<i64>,
+    /// Wayland-native windows (sway only)
+    #[serde(default)]
+    app_id: Option<String>,
+    /// X11 window id, set for i3 and Xwayland windows
+    #[serde(default)]
+    window: Option<i64>,
+    #[serde(default)]
+    window_properties: Option<WindowProperties>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    floating_nodes: Vec<Node>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct WindowProperties {
+    #[serde(default)]
+    class: Option<String>,
+}
+
+impl Node {
+    fn is_window(&self) -> bool {
+        matches!(self.node_type.as_str(), "con" | "floating_con")
+            && self.nodes.is_empty()
+            && self.floating_nodes.is_empty()
+            && (self.app_id.is_some() || self.window.is_some())
+    }
+
+    fn class(&self) -> String {
+        self.app_id
+            .clone()
+            .or_else(|| {
+                self.window_properties
+                    .as_ref()
+                    .and_then(|p| p.class.clone())
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Sway / i3 backend speaking the i3 IPC binary protocol on `$SWAYSOCK` or `$I3SOCK`
+#[derive(Debug)]
+pub struct Sway {
+    socket_path: PathBuf,
+    state: Arc<Mutex<WmState>>,
+}
+
+impl Sway {
+    pub fn from_env() -> Option<Self> {
+        let socket_path = std::env::var("SWAYSOCK")
+            .or_else(|_| std::env::var("I3SOCK"))
+            .ok()?;
+        Some(Self::with_socket_path(socket_path))
+    }
+
+    pub fn with_socket_path(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            state: Arc::new(Mutex::new(WmState::default())),
+        }
+    }
+
+    /// Re-fetch workspaces and the layout tree
+    pub fn refresh(&self) {
+        super::store_state("SWAY", &self.state, fetch_state(&self.socket_path));
+    }
+
+    /// Subscribe to workspace and window events, refreshing state whenever one arrives
+    pub fn start_event_listener(&self) {
+        let socket_path = self.socket_path.clone();
+
+        super::spawn_event_loop("SWAY", Arc::downgrade(&self.state), move |state| {
+            let mut stream = UnixStream::connect(&socket_path)?;
+            write_message(&mut stream, SUBSCRIBE, br#"["workspace","window"]"#)?;
+            let (_, reply) = read_message(&mut stream)?;
+            let reply: serde_json::Value = serde_json::from_slice(&reply)?;
+            if reply.get("success").and_then(|s| s.as_bool()) != Some(true) {
+                return Err(color_eyre::eyre::eyre!("Subscription rejected: {}", reply));
+            }
+
+            loop {
+                read_message(&mut stream)?;
+                let Some(state) = state.upgrade() else {
+                    return Ok(());
+                };
+                super::store_state("SWAY", &state, fetch_state(&socket_path));
+            }
+        });
+    }
+}
+
+impl WindowManager for Sway {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn state(&self) -> WmState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+fn write_message(stream: &mut UnixStream, msg_type: u32, payload: &[u8]) -> std::io::Result<()> {
+    let mut message = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+    message.extend_from_slice(MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&msg_type.to_ne_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message)
+}
+
+fn read_message(stream: &mut UnixStream) -> color_eyre::Result<(u32, Vec<u8>)> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != MAGIC {
+        return Err(color_eyre::eyre::eyre!("Invalid i3 IPC magic"));
+    }
+
+    let len = u32::from_ne_bytes(header[6..10].try_into()?) as usize;
+    let msg_type = u32::from_ne_bytes(header[10..14].try_into()?);
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    Ok((msg_type, payload))
+}
+
+fn request<T: serde::de::DeserializeOwned>(
+    socket_path: &Path,
+    msg_type: u32,
+) -> color_eyre::Result<T> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    write_message(&mut stream, msg_type, b"")?;
+    let (_, payload) = read_message(&mut stream)?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+fn fetch_state(socket_path: &Path) -> color_eyre::Result<WmState> {
+    let workspaces: Vec<I3Workspace> = request(socket_path, GET_WORKSPACES)?;
+    let tree: Node = request(socket_path, GET_TREE)?;
+
+    let mut state = WmState {
+        focused_workspace: workspaces.iter().find(|w| w.focused).map(|w| w.id),
+        workspaces: workspaces
+            .into_iter()
+            .map(|w| Workspace {
+                id: w.id,
+                number: w.num,
+                name: w.name,
+                output: w.output,
+                visible: w.visible,
+                special: false,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    collect_windows(&tree, None, &mut state);
+    Ok(state)
+}
+
+/// Walk the layout tree, attributing each window to its enclosing workspace
+fn collect_windows(node: &Node, workspace_id: Option<i64>, state: &mut WmState) {
+    let mut workspace_id = workspace_id;
+    if node.node_type == "workspace" {
+        workspace_id = Some(node.id);
+
+        if node.name.as_deref() == Some(SCRATCHPAD) {
+            state.workspaces.push(Workspace {
+                id: node.id,
+                number: -1,
+                name: SCRATCHPAD.to_string(),
+                output: String::new(),
+                visible: false,
+                special: true,
+            });
+        }
+    }
+
+    if node.is_window()
+        && let Some(workspace_id) = workspace_id
+    {
+        let id = node.id.to_string();
+        if node.focused {
+            state.focused_window = Some(id.clone());
+        }
+        state.windows.push(Window {
+            id,
+            class: node.class(),
+            title: node.name.clone().unwrap_or_default(),
+            pid: node.pid.and_then(|p| u32::try_from(p).ok()),
+            workspace_id,
+            urgent: node.urgent,
+        });
+    }
+
+    for child in node.nodes.iter().chain(&node.floating_nodes) {
+        collect_windows(child, workspace_id, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    const TREE: &str = r#"{
+        "id": 1, "type": "root", "nodes": [
+            {"id": 2, "type": "output", "name": "__i3", "nodes": [
+                {"id": 3, "type": "workspace", "name": "__i3_scratch", "nodes": [],
+                 "floating_nodes": [
+                    {"id": 30, "type": "floating_con", "name": "notes", "app_id": "obsidian", "pid": 7, "nodes": []}
+                 ]}
+            ]},
+            {"id": 4, "type": "output", "name": "DP-1", "nodes": [
+                {"id": 10, "type": "workspace", "name": "1", "num": 1, "nodes": [
+                    {"id": 11, "type": "con", "name": "vim", "app_id": "kitty", "pid": 42, "focused": true, "nodes": []},
+                    {"id": 12, "type": "con", "name": "Mozilla Firefox", "window": 1234, "urgent": true,
+                     "window_properties": {"class": "firefox"}, "nodes": []}
+                ]}
+            ]}
+        ]
+    }"#;
+
+    fn workspaces(focused: i64) -> String {
+        format!(
+            r#"[{{"id":10,"num":1,"name":"1","focused":{},"visible":{},"output":"DP-1"}},
+                {{"id":20,"num":2,"name":"2:web","focused":{},"visible":{},"output":"DP-1"}}]"#,
+            focused == 10,
+            focused == 10,
+            focused == 20,
+            focused == 20
+        )
+    }
+
+    /// Answer GET_WORKSPACES/GET_TREE requests and hand subscribed streams back to the test
+    fn spawn_fake_sway(path: &Path, focused: Arc<Mutex<i64>>) -> mpsc::Receiver<UnixStream> {
+        let listener = UnixListener::bind(path).unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let Ok((msg_type, _)) = read_message(&mut stream) else {
+                    continue;
+                };
+                match msg_type {
+                    GET_WORKSPACES => {
+                        let payload = workspaces(*focused.lock().unwrap());
+                        let _ = write_message(&mut stream, GET_WORKSPACES, payload.as_bytes());
+                    }
+                    GET_TREE => {
+                        let _ = write_message(&mut stream, GET_TREE, TREE.as_bytes());
+                    }
+                    SUBSCRIBE => {
+                        let _ = write_message(&mut stream, SUBSCRIBE, br#"{"success":true}"#);
+                        let _ = tx.send(stream);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        rx
+    }
+
+    #[test]
+    fn test_fetch_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sway.sock");
+        let _subscribers = spawn_fake_sway(&path, Arc::new(Mutex::new(10)));
+
+        let sway = Sway::with_socket_path(&path);
+        sway.refresh();
+        let state = sway.state();
+
+        assert_eq!(state.focused_workspace, Some(10));
+        assert_eq!(state.focused_output(), Some("DP-1"));
+        assert!(state.workspaces[0].visible && !state.workspaces[1].visible);
+        assert_eq!(state.workspaces[1].name, "2:web");
+        assert!(state.workspace(3).is_some_and(|w| w.special));
+
+        let focused = state.focused_window().unwrap();
+        assert_eq!(focused.class, "kitty");
+        assert_eq!(focused.pid, Some(42));
+        assert_eq!(focused.workspace_id, 10);
+
+        let firefox = state.windows.iter().find(|w| w.id == "12").unwrap();
+        assert_eq!(firefox.class, "firefox");
+        assert_eq!(firefox.pid, None);
+        assert!(firefox.urgent);
+
+        let notes = state.windows.iter().find(|w| w.id == "30").unwrap();
+        assert_eq!(notes.workspace_id, 3);
+    }
+
+    #[test]
+    fn test_events_trigger_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sway.sock");
+        let focused = Arc::new(Mutex::new(10));
+        let subscribers = spawn_fake_sway(&path, focused.clone());
+
+        let sway = Sway::with_socket_path(&path);
+        sway.refresh();
+        sway.start_event_listener();
+
+        let mut events = subscribers.recv_timeout(Duration::from_secs(5)).unwrap();
+        *focused.lock().unwrap() = 20;
+        write_message(&mut events, 0x8000_0000, br#"{"change":"focus"}"#).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sway.state().focused_workspace != Some(20) {
+            assert!(Instant::now() < deadline, "state was not refreshed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Querying wireless interfaces through the kernel's wireless extensions ioctls
+//!
+//! cfg80211 still answers these for every modern driver, which saves speaking nl80211 over
+//! generic netlink for the handful of values the bar shows.
+
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::raw::c_void;
+use std::path::Path;
+
+const SIOCGIWFREQ: libc::c_ulong = 0x8B05;
+const SIOCGIWESSID: libc::c_ulong = 0x8B1B;
+const SIOCGIWRATE: libc::c_ulong = 0x8B21;
+const IW_ESSID_MAX_SIZE: usize = 32;
+/// cfg80211 reports link quality out of 70
+const MAX_LINK_QUALITY: f32 = 70.0;
+
+/// `struct iw_point`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IwPoint {
+    pointer: *mut c_void,
+    length: u16,
+    flags: u16,
+}
+
+/// `struct iw_freq`: `m * 10^e` Hz, or a channel number when it comes out below 1000
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IwFreq {
+    m: i32,
+    e: i16,
+    _i: u8,
+    _flags: u8,
+}
+
+/// `struct iw_param`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IwParam {
+    value: i32,
+    _fixed: u8,
+    disabled: u8,
+    _flags: u16,
+}
+
+/// `union iwreq_data`, of which we only use some members
+#[repr(C)]
+union IwReqData {
+    point: IwPoint,
+    freq: IwFreq,
+    bitrate: IwParam,
+    _size: [u8; 16],
+}
+
+/// `struct iwreq`
+#[repr(C)]
+struct IwReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    data: IwReqData,
+}
+
+impl IwReq {
+    fn new(interface: &str) -> Option<Self> {
+        if interface.len() >= libc::IFNAMSIZ {
+            return None;
+        }
+        let mut name = [0; libc::IFNAMSIZ];
+        for (dst, src) in name.iter_mut().zip(interface.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        Some(Self {
+            name,
+            data: IwReqData { _size: [0; 16] },
+        })
+    }
+}
+
+/// Issue a wireless extensions `request` on `interface`
+fn ioctl(request: libc::c_ulong, iwreq: &mut IwReq) -> Option<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return None;
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let result = unsafe {
+        libc::ioctl(
+            std::os::fd::AsRawFd::as_raw_fd(&socket),
+            request as _,
+            iwreq as *mut IwReq,
+        )
+    };
+    (result >= 0).then_some(())
+}
+
+/// The network `interface` is associated with, or `None` if it isn't wireless or connected
+pub fn ssid(interface: &str) -> Option<String> {
+    let mut buffer = [0u8; IW_ESSID_MAX_SIZE + 1];
+    let mut iwreq = IwReq::new(interface)?;
+    iwreq.data.point = IwPoint {
+        pointer: buffer.as_mut_ptr().cast(),
+        length: buffer.len() as u16,
+        flags: 0,
+    };
+    ioctl(SIOCGIWESSID, &mut iwreq)?;
+
+    let length = unsafe { iwreq.data.point.length } as usize;
+    let ssid = String::from_utf8_lossy(&buffer[..length.min(IW_ESSID_MAX_SIZE)])
+        .trim_end_matches('\0')
+        .to_string();
+    (!ssid.is_empty()).then_some(ssid)
+}
+
+/// The frequency `interface` is connected on, in MHz
+pub fn frequency(interface: &str) -> Option<u32> {
+    let mut iwreq = IwReq::new(interface)?;
+    ioctl(SIOCGIWFREQ, &mut iwreq)?;
+    let freq = unsafe { iwreq.data.freq };
+    let hertz = freq.m as f64 * 10f64.powi(freq.e as i32);
+    (hertz >= 1000.0).then(|| (hertz / 1e6).round() as u32)
+}
+
+/// The bitrate of the last transmitted frame, in bits per second
+pub fn bitrate(interface: &str) -> Option<u32> {
+    let mut iwreq = IwReq::new(interface)?;
+    ioctl(SIOCGIWRATE, &mut iwreq)?;
+    let bitrate = unsafe { iwreq.data.bitrate };
+    (bitrate.disabled == 0 && bitrate.value > 0).then_some(bitrate.value as u32)
+}
+
+/// `2.4 GHz`, `5 GHz` or `6 GHz`
+pub fn band(frequency_mhz: u32) -> Option<&'static str> {
+    match frequency_mhz {
+        2400..=2500 => Some("2.4 GHz"),
+        4900..=5924 => Some("5 GHz"),
+        5925..=7125 => Some("6 GHz"),
+        _ => None,
+    }
+}
+
+/// One row of `/proc/net/wireless`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub interface: String,
+    /// Link quality in percent
+    pub quality: u32,
+    /// Signal level in dBm
+    pub level: i32,
+}
+
+/// Read `proc_root/net/wireless`, which only lists associated interfaces
+pub fn read_signals(proc_root: &Path) -> Vec<Signal> {
+    std::fs::read_to_string(proc_root.join("net/wireless"))
+        .map(|content| parse_signals(&content))
+        .unwrap_or_default()
+}
+
+/// Parse the table after its two header lines
+pub fn parse_signals(content: &str) -> Vec<Signal> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, fields) = line.split_once(':')?;
+            let mut fields = fields.split_whitespace().skip(1);
+            // Values carry a trailing `.` when they were updated since the last read
+            let mut next = || fields.next()?.trim_end_matches('.').parse::<f32>().ok();
+            let (link, level) = (next()?, next()?);
+            Some(Signal {
+                interface: interface.trim().to_string(),
+                quality: (link / MAX_LINK_QUALITY * 100.0).clamp(0.0, 100.0).round() as u32,
+                level: level as i32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signals() {
+        let content = "\
+Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlp3s0: 0000   54.  -56.  -256        0      0      0      0     19        0
+";
+        assert_eq!(
+            parse_signals(content),
+            [Signal {
+                interface: "wlp3s0".to_string(),
+                quality: 77,
+                level: -56,
+            }]
+        );
+        assert_eq!(band(2437), Some("2.4 GHz"));
+        assert_eq!(band(5180), Some("5 GHz"));
+        assert_eq!(band(5955), Some("6 GHz"));
+    }
+
+    #[test]
+    fn test_not_wireless() {
+        assert_eq!(ssid("lo"), None);
+        assert_eq!(ssid("catfood-missing0"), None);
+        assert_eq!(ssid("a-name-far-too-long-for-ifnamsiz"), None);
+        assert_eq!(frequency("lo"), None);
+        assert_eq!(bitrate("lo"), None);
+    }
+}